@@ -1,24 +1,78 @@
 use anyhow::Result;
 use clap::Parser;
-use serde::Serialize;
+use futures_util::StreamExt;
 use std::path::{Path, PathBuf};
-use transport::{result::stat::FileType, AdbTransports};
-use transport::result::device::Devices;
+use std::time::Duration;
+use transport::options::screenrecord::ScreenRecordOptions;
+use transport::options::wait::{WaitForOptions, WaitState, WaitTransport};
+use transport::result::bugreport::BugreportEvent;
+use transport::result::connect::PairOutcome;
+use transport::result::mdns::MdnsCheck;
+use transport::options::retry::RetryPolicy;
+use transport::result::track::{DeviceEvent, TrackerEvent};
+use transport::{result::stat::FileType, AdbTransportError, AdbTransports, DeviceSelector};
+use transport::result::device::Device;
 #[derive(Parser, Debug)]
 #[clap(about, version, author)]
 struct Arguments {
     /// Serial number of the device to connect to
     #[clap(short, long)]
     pub serial: Option<String>,
-    /// Unix socket to connect to server.
-    #[clap(long, default_value = "/var/run/adb.sock")]
+    /// Transport id of the device to connect to
+    #[clap(short = 't', long, conflicts_with_all = &["serial", "usb", "emulator"])]
+    pub transport_id: Option<u64>,
+    /// Use the only device connected over USB
+    #[clap(short = 'd', long, conflicts_with_all = &["serial", "emulator"])]
+    pub usb: bool,
+    /// Use the only emulator or TCP/IP device
+    #[clap(short = 'e', long, conflicts_with = "serial")]
+    pub emulator: bool,
+    /// Server address: tcp:host:port, localfilesystem:/path, localabstract:name, tls://host:port or a path
+    #[clap(long, env = "ADB_SERVER_SOCKET", default_value = "/var/run/adb.sock")]
     pub socket: String,
+    /// PEM file with the CAs that sign the TLS server certificate
+    #[cfg(feature = "tls")]
+    #[clap(long)]
+    pub tls_ca: Option<PathBuf>,
+    /// PEM client certificate for TLS servers that require one
+    #[cfg(feature = "tls")]
+    #[clap(long, requires = "tls-key")]
+    pub tls_cert: Option<PathBuf>,
+    /// PEM private key of --tls-cert
+    #[cfg(feature = "tls")]
+    #[clap(long, requires = "tls-cert")]
+    pub tls_key: Option<PathBuf>,
     /// Use JSON output
     #[clap(long)]
     pub json: bool,
     #[clap(subcommand)]
     pub command: SubCommand,
 }
+impl Arguments {
+    fn selector(&self) -> DeviceSelector {
+        if let Some(transport_id) = self.transport_id {
+            DeviceSelector::TransportId(transport_id)
+        } else if self.usb {
+            DeviceSelector::Usb
+        } else if self.emulator {
+            DeviceSelector::Local
+        } else {
+            DeviceSelector::from(self.serial.clone())
+        }
+    }
+    async fn connect(&self) -> transport::Result<AdbTransports> {
+        let builder = AdbTransports::builder()
+            .address(self.socket.parse()?)
+            .device(self.selector());
+        #[cfg(feature = "tls")]
+        let builder = builder.tls(transport::TlsOptions {
+            ca_file: self.tls_ca.clone(),
+            cert_file: self.tls_cert.clone(),
+            key_file: self.tls_key.clone(),
+        });
+        builder.build().await
+    }
+}
 #[derive(Parser, Debug)]
 pub enum SubCommand {
     /// List connected devices
@@ -26,6 +80,18 @@ pub enum SubCommand {
         /// List devices in long format
         #[clap(short, long)]
         long: bool,
+        /// Print the list exactly as the adb server sent it, even with --json
+        #[clap(long)]
+        raw: bool,
+    },
+    /// Print device connection changes until interrupted
+    TrackDevices {
+        /// Track devices in long format
+        #[clap(short, long)]
+        long: bool,
+        /// Keep tracking across adb server restarts
+        #[clap(long)]
+        reconnect: bool,
     },
     /// Run a shell command on a device
     Shell {
@@ -46,6 +112,145 @@ pub enum SubCommand {
         /// Local path to file
         filename: String,
     },
+    /// Record the device screen as raw H.264 until Ctrl-C or the time limit
+    Screenrecord {
+        /// Local path to output file
+        filename: String,
+        /// Video size, e.g. 1280x720
+        #[clap(long, parse(try_from_str = parse_size))]
+        size: Option<(u32, u32)>,
+        /// Video bit rate in bits per second
+        #[clap(long)]
+        bit_rate: Option<u32>,
+        /// Maximum recording time in seconds (at most 180)
+        #[clap(long)]
+        time_limit: Option<u64>,
+    },
+    /// Wait for a device to reach a state
+    WaitFor {
+        /// Transport to wait on: any, usb or local
+        #[clap(long, default_value = "any")]
+        transport: WaitTransport,
+        /// State to wait for: device, recovery, rescue, sideload, bootloader or disconnect
+        #[clap(long, default_value = "device")]
+        state: WaitState,
+        /// Give up after this many seconds
+        #[clap(long)]
+        timeout: Option<u64>,
+        /// Also wait for sys.boot_completed=1
+        #[clap(long)]
+        boot_completed: bool,
+    },
+    /// Connect to a device over TCP/IP
+    Connect {
+        /// Device address as HOST[:PORT], the port defaults to 5555
+        #[clap(required_unless_present = "mdns")]
+        address: Option<String>,
+        /// Connect to the device advertising this mDNS instance name instead
+        #[clap(long, conflicts_with = "address")]
+        mdns: Option<String>,
+    },
+    /// Disconnect a TCP/IP device, or all of them
+    Disconnect {
+        /// Device address as HOST:PORT
+        address: Option<String>,
+    },
+    /// Pair with a device using wireless debugging
+    Pair {
+        /// Pairing address as HOST:PORT, or the mDNS instance name with --mdns
+        address: String,
+        /// Pairing code shown on the device
+        code: String,
+        /// Treat the address as an mDNS instance name
+        #[clap(long)]
+        mdns: bool,
+    },
+    /// Query the server's mDNS discovery
+    Mdns {
+        #[clap(subcommand)]
+        command: MdnsCommand,
+    },
+    /// Capture a full bugreport zip
+    Bugreport {
+        /// Local path to output zip
+        #[clap(default_value = "bugreport.zip")]
+        filename: String,
+    },
+    /// Forward a local port to the JDWP thread of a process, or list debuggable processes
+    Jdwp {
+        /// Process to debug, lists debuggable processes until interrupted when omitted
+        pid: Option<u32>,
+        /// Local port for the debugger to attach to
+        #[clap(long, default_value = "8700")]
+        listen: u16,
+        /// List processes with their package names instead of bare pids
+        #[clap(long, conflicts_with = "pid")]
+        apps: bool,
+    },
+    /// Install an APK, replacing an installed version
+    Install {
+        /// Local path to the APK
+        apk: PathBuf,
+    },
+    /// Print the device log
+    Logcat {
+        /// Arguments passed to logcat, e.g. -d to dump the log and exit
+        #[clap(allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+#[derive(Parser, Debug)]
+pub enum MdnsCommand {
+    /// Check whether mDNS discovery is available
+    Check,
+    /// List discovered adb services
+    Services,
+}
+fn parse_size(size: &str) -> Result<(u32, u32)> {
+    let (width, height) = size
+        .split_once('x')
+        .ok_or_else(|| anyhow::anyhow!("size must be WIDTHxHEIGHT"))?;
+    Ok((width.parse()?, height.parse()?))
+}
+
+/// Prints `devices` in aligned columns, with the details of `adb devices -l` when `long`.
+fn print_device_table(devices: &[Device], long: bool) {
+    let mut rows = vec![vec![String::from("SERIAL"), String::from("STATE")]];
+    if long {
+        rows[0].extend(["TRANSPORT_ID", "PRODUCT", "MODEL", "DEVICE"].map(String::from));
+    }
+    for device in devices {
+        let mut row = vec![device.serial.clone(), device.state.to_string()];
+        if long {
+            let field = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+            row.push(
+                device
+                    .transport_id
+                    .map_or_else(|| "-".to_string(), |id| id.to_string()),
+            );
+            row.extend([
+                field(&device.product),
+                field(&device.model),
+                field(&device.device),
+            ]);
+        }
+        rows.push(row);
+    }
+    let mut widths = vec![0; rows[0].len()];
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    for row in rows {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", line.trim_end());
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -56,28 +261,67 @@ struct FileItem {
 #[tokio::main]
 async fn main() {
     let args = Arguments::parse();
-    let mut adb = transport::AdbTransports::new(args.socket, args.json)
-        .await
-        .unwrap();
+    let mut adb = args.connect().await.unwrap();
+    let selector = adb.default_selector();
     match args.command {
-        SubCommand::Devices { long } => {
+        SubCommand::Devices { long, raw } => {
+            if raw {
+                println!("List of devices attached");
+                println!("{}", adb.raw_devices(long).await.unwrap());
+                return;
+            }
             let devices = if long {
                 adb.devices_long().await.unwrap()
             } else {
                 adb.devices().await.unwrap()
             };
-            
-
-            match devices {
-                Devices::Devices(devices) => {
-                    // devices.iter().for_each(|device| {
-                    //     println!("{:?}", device);
-                    // });
-                    print!("{}", serde_json::to_string(&devices).unwrap());
+            if args.json {
+                print!("{}", serde_json::to_string(&devices).unwrap());
+            } else {
+                print_device_table(&devices, long);
+            }
+        }
+        SubCommand::TrackDevices { long, reconnect } => {
+            let events = if reconnect {
+                adb.track_devices_resilient(long, RetryPolicy::default())
+                    .await
+                    .unwrap()
+                    .boxed_local()
+            } else if long {
+                adb.track_devices_long()
+                    .await
+                    .unwrap()
+                    .map(|event| event.map(TrackerEvent::Device))
+                    .boxed_local()
+            } else {
+                adb.track_devices()
+                    .await
+                    .unwrap()
+                    .map(|event| event.map(TrackerEvent::Device))
+                    .boxed_local()
+            };
+            let mut events = events.take_until(Box::pin(tokio::signal::ctrl_c()));
+            while let Some(event) = events.next().await {
+                let event = event.unwrap();
+                if args.json {
+                    println!("{}", serde_json::to_string(&event).unwrap());
+                    continue;
                 }
-                Devices::Raw(raw) => {
-                    println!("List of devices attached");
-                    println!("{}", raw);
+                match event {
+                    TrackerEvent::Device(DeviceEvent::Added(device)) => {
+                        println!("+ {}\t{}", device.serial, device.state)
+                    }
+                    TrackerEvent::Device(DeviceEvent::Removed(device)) => {
+                        println!("- {}", device.serial)
+                    }
+                    TrackerEvent::Device(DeviceEvent::StateChanged { device, old }) => println!(
+                        "~ {}\t{} -> {}",
+                        device.serial,
+                        old,
+                        device.state
+                    ),
+                    TrackerEvent::ServerDisconnected => println!("! server disconnected"),
+                    TrackerEvent::ServerReconnected => println!("! server reconnected"),
                 }
             }
         }
@@ -85,7 +329,9 @@ async fn main() {
             let callback = |str: Vec<u8>| {
                 std::io::Write::write_all(&mut std::io::stdout(), &str).unwrap();
             };
-            adb.shell(args.serial, command, callback).await.unwrap();
+            adb.shell(selector, command, callback, std::future::pending())
+                .await
+                .unwrap();
         }
         SubCommand::Push { path, filename } => {
             let path = Path::new(&path);
@@ -99,11 +345,13 @@ async fn main() {
             if path.is_file() {
                 let file = std::fs::File::open(path).unwrap();
                 let mut reader = std::io::BufReader::new(file);
-                adb.push(args.serial, &mut reader, filename).await.unwrap();
+                adb.push(selector, &mut reader, filename, std::future::pending())
+                    .await
+                    .unwrap();
             } else if path.is_dir() {
                 //check if `filename` is a dir?
                 let remote_type = adb
-                    .sync_stat(filename.clone(), args.serial.clone())
+                    .sync_stat(filename.clone(), selector.clone())
                     .await
                     .unwrap()
                     .get_file_type();
@@ -124,9 +372,10 @@ async fn main() {
                         let filename_ = path.strip_prefix(base).unwrap();
                         let filename_ = filename.join(filename_);
                         adb.push(
-                            args.serial.clone(),
+                            selector.clone(),
                             &mut reader,
                             filename_.to_str().unwrap(),
+                            std::future::pending(),
                         )
                         .await
                         .unwrap();
@@ -137,10 +386,10 @@ async fn main() {
                         )
                     } else if path.is_dir() {
                         let filename_ = path.strip_prefix(base).unwrap();
-                        let filename_ = filename.join(filename_);
+                        let _filename_ = filename.join(filename_);
                         // println!("mkdir {}", filename_.to_str().unwrap());
 
-                        // adb.mkdir(args.serial.clone(), filename.to_str().unwrap())
+                        // adb.mkdir(selector.clone(), filename.to_str().unwrap())
                         //     .await
                         //     .unwrap();
                     }
@@ -149,7 +398,7 @@ async fn main() {
         }
         SubCommand::Pull { path, filename } => {
             let stat_info = adb
-                .sync_stat(path.clone(), args.serial.clone())
+                .sync_stat(path.clone(), selector.clone())
                 .await
                 .unwrap();
             let remote_type = stat_info.get_file_type();
@@ -173,13 +422,13 @@ async fn main() {
                     &PathBuf::from(path.clone()),
                     &PathBuf::from(filename.clone()),
                     &mut adb,
-                    args.serial.clone(),
+                    selector.clone(),
                 )
                 .await
                 .unwrap();
             // dbg!(dirs);
             // dbg!(files);
-                walk_pull(dirs, files, args.serial.clone(), &mut adb).await.unwrap();
+                walk_pull(dirs, files, selector.clone(), &mut adb).await.unwrap();
 
                 return;
             }
@@ -191,16 +440,15 @@ async fn main() {
                     println!("File already exists");
                     filename_.to_path_buf()
                 } else if filename_.is_dir() {
-                    let new_filename = filename_.join(remote_file);
-                    new_filename
+                    filename_.join(remote_file)
                 } else {
-                    Err(anyhow::anyhow!("Path is not allowed")).unwrap()
+                    panic!("Path is not allowed")
                 }
             } else {
                 filename_
             };
             let mut file = std::fs::File::create(filename_).unwrap();
-            adb.pull(args.serial, path.clone(), &mut file)
+            adb.pull(selector, path.clone(), &mut file, std::future::pending())
                 .await
                 .unwrap();
 
@@ -208,13 +456,201 @@ async fn main() {
                 println!("Pulled file {} to {}", path, filename);
             }
         }
+        SubCommand::Screenrecord {
+            filename,
+            size,
+            bit_rate,
+            time_limit,
+        } => {
+            let options = ScreenRecordOptions {
+                size,
+                bit_rate,
+                time_limit: time_limit.map(Duration::from_secs),
+            };
+            let mut file = tokio::fs::File::create(&filename).await.unwrap();
+            let cancel = async {
+                let _ = tokio::signal::ctrl_c().await;
+            };
+            let written = adb
+                .screenrecord(selector, options, &mut file, cancel)
+                .await
+                .unwrap();
+            println!("Recorded {} bytes to {}", written, filename);
+        }
+        SubCommand::WaitFor {
+            transport,
+            state,
+            timeout,
+            boot_completed,
+        } => {
+            let options = WaitForOptions {
+                transport,
+                state,
+                timeout: timeout.map(Duration::from_secs),
+                boot_completed,
+            };
+            adb.wait_for(selector, options).await.unwrap();
+        }
+        SubCommand::Connect { address, mdns } => {
+            let outcome = match (address, mdns) {
+                (_, Some(instance)) => mdns_or_exit(adb.connect_mdns(instance).await),
+                (Some(address), None) => adb.connect(address).await.unwrap(),
+                (None, None) => unreachable!("clap requires an address or --mdns"),
+            };
+            println!("{}", outcome);
+            if !outcome.is_connected() {
+                std::process::exit(1);
+            }
+        }
+        SubCommand::Disconnect { address } => {
+            println!("{}", adb.disconnect(address).await.unwrap());
+        }
+        SubCommand::Pair {
+            address,
+            code,
+            mdns,
+        } => {
+            let outcome = if mdns {
+                mdns_or_exit(adb.pair_mdns(code, address).await)
+            } else {
+                adb.pair(code, address).await.unwrap()
+            };
+            println!("{}", outcome);
+            if let PairOutcome::Failed(_) = outcome {
+                std::process::exit(1);
+            }
+        }
+        SubCommand::Mdns { command } => match command {
+            MdnsCommand::Check => match adb.mdns_check().await.unwrap() {
+                MdnsCheck::Available(version) => println!("mdns daemon version [{}]", version),
+                MdnsCheck::Unavailable(message) => {
+                    println!("{}", message);
+                    std::process::exit(1);
+                }
+            },
+            MdnsCommand::Services => {
+                let services = adb.mdns_services().await.unwrap();
+                if args.json {
+                    print!("{}", serde_json::to_string(&services).unwrap());
+                } else {
+                    println!("List of discovered mdns services");
+                    for service in services {
+                        println!(
+                            "{}\t{}\t{}",
+                            service.instance, service.service_type, service.address
+                        );
+                    }
+                }
+            }
+        },
+        SubCommand::Install { apk } => {
+            let mut device = adb.device(selector).await.unwrap();
+            device.install(&apk).await.unwrap();
+            println!("{}: installed", apk.display());
+        }
+        SubCommand::Logcat { args } => {
+            let mut device = adb.device(selector).await.unwrap();
+            let callback = |str: Vec<u8>| {
+                std::io::Write::write_all(&mut std::io::stdout(), &str).unwrap();
+            };
+            device
+                .logcat(args, callback, std::future::pending())
+                .await
+                .unwrap();
+        }
+        SubCommand::Bugreport { filename } => {
+            let mut file = std::fs::File::create(&filename).unwrap();
+            let json = args.json;
+            let progress = |event: &BugreportEvent| {
+                if json {
+                    println!("{}", serde_json::to_string(event).unwrap());
+                    return;
+                }
+                match event {
+                    BugreportEvent::Begin(path) => println!("generating {}", path),
+                    BugreportEvent::Progress { current, total } if *total > 0 => {
+                        println!("[{:>3}%] generating bugreport", current * 100 / total)
+                    }
+                    _ => {}
+                }
+            };
+            let zip = adb
+                .bugreport(selector, &mut file, progress, std::future::pending())
+                .await
+                .unwrap();
+            if !json {
+                println!("{}: pulled to {}", zip, filename);
+            }
+        }
+        SubCommand::Jdwp {
+            pid: Some(pid),
+            listen,
+            ..
+        } => {
+            let listener = tokio::net::TcpListener::bind(("127.0.0.1", listen))
+                .await
+                .unwrap();
+            println!("forwarding 127.0.0.1:{} to jdwp:{}", listen, pid);
+            tokio::select! {
+                result = adb.forward_jdwp(selector, pid, listener) => result.unwrap(),
+                _ = tokio::signal::ctrl_c() => {}
+            }
+        }
+        SubCommand::Jdwp {
+            pid: None,
+            apps: true,
+            ..
+        } => {
+            let processes = adb.track_app(selector).await.unwrap().boxed_local();
+            let mut processes = processes.take_until(Box::pin(tokio::signal::ctrl_c()));
+            while let Some(processes) = processes.next().await {
+                let processes = processes.unwrap();
+                if args.json {
+                    println!("{}", serde_json::to_string(&processes).unwrap());
+                    continue;
+                }
+                for process in processes {
+                    println!(
+                        "{}\t{}\t{}{}",
+                        process.pid,
+                        process.process_name,
+                        process.package_names.join(","),
+                        if process.waiting_for_debugger {
+                            "\t(waiting for debugger)"
+                        } else {
+                            ""
+                        }
+                    );
+                }
+                println!();
+            }
+        }
+        SubCommand::Jdwp { pid: None, .. } => {
+            let pids = adb.track_jdwp(selector).await.unwrap().boxed_local();
+            let mut pids = pids.take_until(Box::pin(tokio::signal::ctrl_c()));
+            while let Some(pids) = pids.next().await {
+                for pid in pids.unwrap() {
+                    println!("{}", pid);
+                }
+            }
+        }
+    }
+}
+/// Reports an unknown mDNS instance like adb does, rather than as a panic.
+fn mdns_or_exit<T>(result: transport::Result<T>) -> T {
+    match result {
+        Err(err @ AdbTransportError::MdnsServiceNotFound(_)) => {
+            eprintln!("error: {}", err);
+            std::process::exit(1);
+        }
+        result => result.unwrap(),
     }
 }
 #[inline]
 async fn walk_pull(
     dir: Vec<PathBuf>,
     files: Vec<FileItem>,
-    serial: Option<String>,
+    selector: DeviceSelector,
     adb: &mut AdbTransports,
 ) -> Result<()> {
     for folder in dir {
@@ -222,7 +658,12 @@ async fn walk_pull(
     }
     for f in files {
         let mut file = std::fs::File::create(f.local_path).unwrap();
-        adb.pull(serial.clone(), f.remote_file.to_str().unwrap(), &mut file)
+        adb.pull(
+            selector.clone(),
+            f.remote_file.to_str().unwrap(),
+            &mut file,
+            std::future::pending(),
+        )
             .await
             .unwrap();
                     {
@@ -232,17 +673,17 @@ async fn walk_pull(
     Ok(())
 }
 #[inline]
-#[async_recursion::async_recursion(?Send)]
+#[async_recursion::async_recursion]
 async fn walk_remote_dirs(
     dir: &mut Vec<PathBuf>,
     files: &mut Vec<FileItem>,
-    rpath: &PathBuf,
-    lpath: &PathBuf,
+    rpath: &Path,
+    lpath: &Path,
     adb: &mut AdbTransports,
-    serial: Option<String>,
+    selector: DeviceSelector,
 ) -> Result<()> {
-    let mut walker = adb
-        .list(rpath.to_str().unwrap().to_string(), serial.clone())
+    let walker = adb
+        .list(rpath.to_str().unwrap().to_string(), selector.clone())
         .await?;
     for item in walker {
         let file_type = FileType::from(item.mode);
@@ -258,7 +699,7 @@ async fn walk_remote_dirs(
                 &rpath.join(item.name.clone()),
                 &lpath.join(item.name.clone()),
                 adb,
-                serial.clone(),
+                selector.clone(),
             )
             .await?;
         } else if file_type == FileType::File {
@@ -0,0 +1,234 @@
+use std::sync::Arc;
+
+use futures_util::future::BoxFuture;
+
+use crate::options::features::FeatureDetection;
+use crate::options::path::PathPolicy;
+use crate::options::retry::RetryPolicy;
+use crate::options::timeout::Timeouts;
+use crate::transport::stream_transport::StreamTransport;
+use crate::transport::transport::AdbTransport;
+use crate::{
+    AbstractConnector, AdbClient, AdbTransports, Connector, DeviceSelector, Result, ServerAddress,
+    SessionState, TcpConnector, UnixConnector,
+};
+#[cfg(feature = "tls")]
+use crate::{TlsConnector, TlsOptions};
+
+/// Default size of the buffers shell output, recordings and uploads are read through.
+pub const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
+
+pub(crate) type OpenTransport =
+    Arc<dyn Fn(Timeouts) -> BoxFuture<'static, Result<Box<dyn AdbTransport>>> + Send + Sync>;
+
+/// Configures an `AdbTransports` before connecting it.
+///
+/// ```no_run
+/// # async fn run() -> transport::Result<()> {
+/// use transport::{AdbTransportsBuilder, DeviceSelector};
+/// let adb = AdbTransportsBuilder::new()
+///     .address("tcp:adb.lab.internal:5037".parse()?)
+///     .device(DeviceSelector::Serial("emulator-5554".to_string()))
+///     .build()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct AdbTransportsBuilder {
+    address: ServerAddress,
+    connector: Option<OpenTransport>,
+    selector: DeviceSelector,
+    timeouts: Timeouts,
+    retry: Option<RetryPolicy>,
+    path_policy: PathPolicy,
+    buffer_size: usize,
+    features: FeatureDetection,
+    #[cfg(feature = "tls")]
+    tls: TlsOptions,
+}
+impl Default for AdbTransportsBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl AdbTransportsBuilder {
+    /// Starts from the local server at localhost:5037 and the default limits.
+    pub fn new() -> Self {
+        Self {
+            address: ServerAddress::default(),
+            connector: None,
+            selector: DeviceSelector::default(),
+            timeouts: Timeouts::default(),
+            retry: None,
+            path_policy: PathPolicy::default(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            features: FeatureDetection::default(),
+            #[cfg(feature = "tls")]
+            tls: TlsOptions::default(),
+        }
+    }
+    pub fn address(mut self, address: ServerAddress) -> Self {
+        self.address = address;
+        self.connector = None;
+        self
+    }
+    /// Talks to the server over the streams `connector` opens instead of an address.
+    pub fn connector<C: Connector + 'static>(mut self, connector: C) -> Self {
+        self.connector = Some(open_with(connector));
+        self
+    }
+    /// The device `AdbTransports::default_selector` hands out.
+    pub fn device(mut self, selector: DeviceSelector) -> Self {
+        self.selector = selector;
+        self
+    }
+    pub fn timeouts(mut self, timeouts: Timeouts) -> Self {
+        self.timeouts = timeouts;
+        self
+    }
+    /// Retries connecting while the server is unreachable, e.g. still starting up.
+    /// Without a policy the first failure is returned.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+    pub fn path_policy(mut self, policy: PathPolicy) -> Self {
+        self.path_policy = policy;
+        self
+    }
+    /// Uploads send at most 64KiB per chunk whatever the buffer size.
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size.max(1);
+        self
+    }
+    pub fn feature_detection(mut self, features: FeatureDetection) -> Self {
+        self.features = features;
+        self
+    }
+    /// Certificates for `tls://` addresses.
+    #[cfg(feature = "tls")]
+    pub fn tls(mut self, options: TlsOptions) -> Self {
+        self.tls = options;
+        self
+    }
+    #[async_backtrace::framed]
+    pub async fn build(self) -> Result<AdbTransports> {
+        self.client()?.session().await
+    }
+    /// A cloneable handle that connects anew for every operation. The address is checked
+    /// now, the server is first contacted by the first operation.
+    pub fn client(self) -> Result<AdbClient> {
+        let open = match self.connector.clone() {
+            Some(open) => open,
+            None => self.open_address()?,
+        };
+        Ok(AdbClient::new(open, self))
+    }
+    /// Opens a session through `open`, retrying while the server is unreachable.
+    pub(crate) async fn connect(&self, open: &OpenTransport) -> Result<AdbTransports> {
+        let mut failures = 0;
+        let transports = loop {
+            match open(self.timeouts.clone()).await {
+                Ok(transports) => break transports,
+                Err(err) => {
+                    failures += 1;
+                    let delay = self
+                        .retry
+                        .as_ref()
+                        .and_then(|policy| policy.reconnect_delay(failures, &err));
+                    match delay {
+                        Some(delay) => tokio::time::sleep(delay).await,
+                        None => return Err(err),
+                    }
+                }
+            }
+        };
+        Ok(AdbTransports {
+            transports,
+            state: SessionState::Idle,
+            selector: self.selector.clone(),
+            retry: self.retry.clone(),
+            path_policy: self.path_policy.clone(),
+            buffer_size: self.buffer_size,
+            features: self.features.clone(),
+        })
+    }
+    fn open_address(&self) -> Result<OpenTransport> {
+        match self.address.clone() {
+            ServerAddress::Tcp { host, port } => Ok(open_with(TcpConnector::new(host, port))),
+            ServerAddress::LocalFilesystem(path) => Ok(open_with(UnixConnector::new(path))),
+            #[cfg(target_os = "linux")]
+            ServerAddress::LocalAbstract(name) => Ok(open_with(AbstractConnector::new(name))),
+            #[cfg(not(target_os = "linux"))]
+            ServerAddress::LocalAbstract(name) => Err(crate::AdbTransportError::InvalidAddress(
+                format!("localabstract:{} needs Linux", name),
+            )),
+            #[cfg(feature = "tls")]
+            ServerAddress::Tls { host, port } => {
+                Ok(open_with(TlsConnector::new(host, port, &self.tls)?))
+            }
+            #[cfg(not(feature = "tls"))]
+            address @ ServerAddress::Tls { .. } => Err(crate::AdbTransportError::InvalidAddress(
+                format!("{} needs the tls feature", address),
+            )),
+        }
+    }
+}
+fn open_with<C: Connector + 'static>(connector: C) -> OpenTransport {
+    let connector: Arc<dyn Connector<Stream = C::Stream>> = Arc::new(connector);
+    Arc::new(move |timeouts| {
+        let connector = connector.clone();
+        Box::pin(async move {
+            let transport = StreamTransport::with_timeouts(connector, timeouts).await?;
+            Ok(Box::new(transport) as Box<dyn AdbTransport>)
+        })
+    })
+}
+
+#[tokio::test]
+async fn test_build_retries_unreachable_server() {
+    use crate::AdbTransportError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    /// Refuses the first two connections, like a server that is still starting.
+    struct Starting(Arc<AtomicU32>);
+    #[async_trait::async_trait]
+    impl Connector for Starting {
+        type Stream = tokio::io::DuplexStream;
+        async fn connect(&self) -> Result<Self::Stream> {
+            if self.0.fetch_add(1, Ordering::SeqCst) < 2 {
+                return Err(AdbTransportError::unreachable(
+                    std::io::ErrorKind::ConnectionRefused.into(),
+                ));
+            }
+            Ok(tokio::io::duplex(64).0)
+        }
+    }
+    let policy = RetryPolicy {
+        initial_delay: Duration::from_millis(1),
+        max_attempts: Some(3),
+        ..RetryPolicy::default()
+    };
+    let attempts = Arc::new(AtomicU32::new(0));
+    let adb = AdbTransportsBuilder::new()
+        .connector(Starting(attempts.clone()))
+        .retry(policy)
+        .device(DeviceSelector::TransportId(3))
+        .build()
+        .await
+        .unwrap();
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    assert_eq!(adb.default_selector(), DeviceSelector::TransportId(3));
+
+    let attempts = Arc::new(AtomicU32::new(0));
+    let without_retry = AdbTransportsBuilder::new()
+        .connector(Starting(attempts.clone()))
+        .build()
+        .await;
+    assert!(matches!(
+        without_retry,
+        Err(AdbTransportError::ServerUnreachable(_))
+    ));
+    assert_eq!(attempts.load(Ordering::SeqCst), 1);
+}
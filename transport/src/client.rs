@@ -0,0 +1,142 @@
+use std::future::Future;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::builder::OpenTransport;
+use crate::result::dent::SyncDent;
+use crate::result::device::Device;
+use crate::result::stat::StatInfo;
+use crate::{AdbTransports, AdbTransportsBuilder, DeviceHandle, DeviceSelector, Result};
+
+/// A cheap, cloneable handle on an adb server. Every operation runs on its own fresh
+/// connection, so clones can be moved into `tokio::spawn` and used concurrently.
+///
+/// ```no_run
+/// # async fn run() -> transport::Result<()> {
+/// use transport::{AdbTransports, DeviceSelector};
+/// let client = AdbTransports::builder().client()?;
+/// let mut tasks = Vec::new();
+/// for device in client.devices().await? {
+///     let client = client.clone();
+///     tasks.push(tokio::spawn(async move {
+///         let selector = DeviceSelector::Serial(device.serial);
+///         client.shell_output(selector, "getprop ro.product.model").await
+///     }));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AdbClient {
+    open: OpenTransport,
+    config: Arc<AdbTransportsBuilder>,
+}
+impl AdbClient {
+    pub(crate) fn new(open: OpenTransport, config: AdbTransportsBuilder) -> Self {
+        Self {
+            open,
+            config: Arc::new(config),
+        }
+    }
+    /// Connects a session of its own, for a sequence of requests on one connection.
+    #[async_backtrace::framed]
+    pub async fn session(&self) -> Result<AdbTransports> {
+        self.config.connect(&self.open).await
+    }
+    /// Opens a handle on `selector`, see `AdbTransports::device`.
+    #[async_backtrace::framed]
+    pub async fn device(&self, selector: DeviceSelector) -> Result<DeviceHandle> {
+        Ok(DeviceHandle::new(self.session().await?, selector))
+    }
+    #[async_backtrace::framed]
+    pub async fn devices(&self) -> Result<Vec<Device>> {
+        self.session().await?.devices().await
+    }
+    #[async_backtrace::framed]
+    pub async fn devices_long(&self) -> Result<Vec<Device>> {
+        self.session().await?.devices_long().await
+    }
+    #[async_backtrace::framed]
+    pub async fn shell_output<C: ToString>(
+        &self,
+        selector: DeviceSelector,
+        cmd: C,
+    ) -> Result<Vec<u8>> {
+        self.session().await?.shell_output(selector, cmd).await
+    }
+    #[async_backtrace::framed]
+    pub async fn push<A: AsRef<str>>(
+        &self,
+        selector: DeviceSelector,
+        stream: &mut (dyn Read + Send),
+        path: A,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<()> {
+        let mut adb = self.session().await?;
+        adb.push(selector, stream, path, cancel).await
+    }
+    #[async_backtrace::framed]
+    pub async fn pull<A: AsRef<str>>(
+        &self,
+        selector: DeviceSelector,
+        path: A,
+        output: &mut (dyn Write + Send),
+        cancel: impl Future<Output = ()>,
+    ) -> Result<()> {
+        let mut adb = self.session().await?;
+        adb.pull(selector, path, output, cancel).await
+    }
+    #[async_backtrace::framed]
+    pub async fn list<A: AsRef<str>>(
+        &self,
+        selector: DeviceSelector,
+        path: A,
+    ) -> Result<Vec<SyncDent>> {
+        let mut adb = self.session().await?;
+        adb.list(path.as_ref().to_string(), selector).await
+    }
+    #[async_backtrace::framed]
+    pub async fn stat<A: AsRef<str>>(&self, selector: DeviceSelector, path: A) -> Result<StatInfo> {
+        let mut adb = self.session().await?;
+        adb.sync_stat(path.as_ref().to_string(), selector).await
+    }
+    #[async_backtrace::framed]
+    pub async fn install<P: AsRef<Path>>(&self, selector: DeviceSelector, apk: P) -> Result<()> {
+        self.session().await?.install(selector, apk).await
+    }
+}
+
+#[tokio::test]
+async fn test_client_from_tasks() {
+    use crate::transport::stream_transport::FakeServer;
+
+    fn send_sync<T: Send + Sync>() {}
+    send_sync::<AdbClient>();
+    send_sync::<AdbTransports>();
+    send_sync::<DeviceHandle>();
+
+    let server = FakeServer::new(|request| match request {
+        "host:devices" => b"OKAY0015emulator-5554\tdevice\n".to_vec(),
+        _ => b"FAIL0014unknown host service".to_vec(),
+    });
+    let log = server.log.clone();
+    let client = AdbTransports::builder().connector(server).client().unwrap();
+    // Nothing connects until an operation runs.
+    assert!(log.lock().unwrap().is_empty());
+    let tasks: Vec<_> = (0..4)
+        .map(|_| {
+            let client = client.clone();
+            tokio::spawn(async move { client.devices().await })
+        })
+        .collect();
+    for task in tasks {
+        let devices = task.await.unwrap().unwrap();
+        assert_eq!(devices[0].serial, "emulator-5554");
+    }
+    let log = log.lock().unwrap();
+    assert_eq!(
+        log.iter().filter(|entry| *entry == "host:devices").count(),
+        4
+    );
+}
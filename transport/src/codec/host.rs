@@ -0,0 +1,182 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::FrameLength;
+use crate::{AdbCommand, AdbTransportError};
+
+/// Status the server answers a host request with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HostReply {
+    Okay,
+    Fail(String),
+}
+impl HostReply {
+    /// Turns a FAIL into the matching typed error.
+    pub fn into_result(self) -> Result<(), AdbTransportError> {
+        match self {
+            HostReply::Okay => Ok(()),
+            HostReply::Fail(message) => Err(AdbTransportError::from_fail(&message)),
+        }
+    }
+}
+
+/// Encodes host requests and decodes their OKAY/FAIL replies.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HostCodec;
+impl FrameLength for HostCodec {
+    // Four hex digits cap every length at 64KiB.
+    fn frame_len(&self, src: &[u8]) -> Result<usize, AdbTransportError> {
+        Ok(match src.get(..4) {
+            Some(status) if status.eq_ignore_ascii_case(b"FAIL") => {
+                8 + src.get(4..8).and_then(parse_hex_length).unwrap_or(0)
+            }
+            _ => 4,
+        })
+    }
+}
+impl Decoder for HostCodec {
+    type Item = HostReply;
+    type Error = AdbTransportError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        if src.len() < 4 {
+            return Ok(None);
+        }
+        if src[..4].eq_ignore_ascii_case(b"OKAY") {
+            src.advance(4);
+            return Ok(Some(HostReply::Okay));
+        }
+        if !src[..4].eq_ignore_ascii_case(b"FAIL") {
+            return Err(AdbTransportError::InvalidResponse(
+                "status".to_string(),
+                Some(String::from_utf8_lossy(&src[..4]).to_string()),
+            ));
+        }
+        src.reserve(self.frame_len(src)?.saturating_sub(src.len()));
+        let message = match src.get(4..) {
+            Some(payload) => match decode_hex_payload(payload)? {
+                Some(message) => message.to_vec(),
+                None => return Ok(None),
+            },
+            None => return Ok(None),
+        };
+        src.advance(8 + message.len());
+        Ok(Some(HostReply::Fail(
+            String::from_utf8_lossy(&message).to_string(),
+        )))
+    }
+}
+impl Encoder<AdbCommand> for HostCodec {
+    type Error = AdbTransportError;
+
+    fn encode(&mut self, item: AdbCommand, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        HexCodec.encode(item.to_string().as_bytes(), dst)
+    }
+}
+
+/// Encodes and decodes payloads prefixed with their length as four hex digits,
+/// e.g. the reply of `host:devices` or each update of `host:track-devices`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HexCodec;
+impl FrameLength for HexCodec {
+    fn frame_len(&self, src: &[u8]) -> Result<usize, AdbTransportError> {
+        Ok(4 + src.get(..4).and_then(parse_hex_length).unwrap_or(0))
+    }
+}
+impl Decoder for HexCodec {
+    type Item = Bytes;
+    type Error = AdbTransportError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        src.reserve(self.frame_len(src)?.saturating_sub(src.len()));
+        let length = match decode_hex_payload(src)? {
+            Some(payload) => payload.len(),
+            None => return Ok(None),
+        };
+        src.advance(4);
+        Ok(Some(src.split_to(length).freeze()))
+    }
+}
+impl Encoder<&[u8]> for HexCodec {
+    type Error = AdbTransportError;
+
+    fn encode(&mut self, item: &[u8], dst: &mut BytesMut) -> Result<(), Self::Error> {
+        if item.len() > 0xffff {
+            return Err(AdbTransportError::ConversionError(format!(
+                "payload of {} bytes does not fit a hex length",
+                item.len()
+            )));
+        }
+        dst.reserve(4 + item.len());
+        dst.put_slice(format!("{:04x}", item.len()).as_bytes());
+        dst.put_slice(item);
+        Ok(())
+    }
+}
+
+fn parse_hex_length(length: &[u8]) -> Option<usize> {
+    usize::from_str_radix(std::str::from_utf8(length).ok()?, 16).ok()
+}
+/// The payload after a hex length, or None while it is incomplete.
+fn decode_hex_payload(src: &[u8]) -> Result<Option<&[u8]>, AdbTransportError> {
+    let Some(length) = src.get(..4) else {
+        return Ok(None);
+    };
+    let length = parse_hex_length(length).ok_or_else(|| {
+        AdbTransportError::InvalidResponse(
+            "hex_length".to_string(),
+            Some(String::from_utf8_lossy(length).to_string()),
+        )
+    })?;
+    Ok(src.get(4..4 + length))
+}
+
+#[test]
+fn test_host_codec() {
+    let mut buffer = BytesMut::new();
+    HostCodec
+        .encode(
+            AdbCommand::TransportSerial("emulator-5554".to_string()),
+            &mut buffer,
+        )
+        .unwrap();
+    assert_eq!(&buffer[..], b"001chost:transport:emulator-5554");
+
+    let mut buffer = BytesMut::from(&b"OKAYshell output"[..]);
+    assert_eq!(HostCodec.frame_len(&buffer).unwrap(), 4);
+    assert_eq!(
+        HostCodec.decode(&mut buffer).unwrap(),
+        Some(HostReply::Okay)
+    );
+    assert_eq!(&buffer[..], b"shell output");
+
+    let mut buffer = BytesMut::from(&b"FAIL000edevice off"[..]);
+    assert_eq!(HostCodec.frame_len(&buffer).unwrap(), 22);
+    assert_eq!(HostCodec.decode(&mut buffer).unwrap(), None);
+    buffer.extend_from_slice(b"line");
+    assert_eq!(
+        HostCodec.decode(&mut buffer).unwrap(),
+        Some(HostReply::Fail("device offline".to_string()))
+    );
+    assert!(buffer.is_empty());
+
+    assert!(HostCodec.decode(&mut BytesMut::from(&b"0012"[..])).is_err());
+    assert!(HostCodec
+        .decode(&mut BytesMut::from(&[0xff, 0xfe, 0x00, 0x80][..]))
+        .is_err());
+}
+#[test]
+fn test_hex_codec() {
+    let mut buffer = BytesMut::from(&b"0015emulator-5554\tdevice\n0000"[..]);
+    assert_eq!(HexCodec.frame_len(&buffer).unwrap(), 25);
+    assert_eq!(
+        HexCodec.decode(&mut buffer).unwrap().unwrap(),
+        &b"emulator-5554\tdevice\n"[..]
+    );
+    assert_eq!(HexCodec.decode(&mut buffer).unwrap().unwrap(), &b""[..]);
+    assert_eq!(
+        HexCodec.decode(&mut BytesMut::from(&b"00"[..])).unwrap(),
+        None
+    );
+    assert!(HexCodec.decode(&mut BytesMut::from(&b"zzzz"[..])).is_err());
+}
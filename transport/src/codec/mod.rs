@@ -0,0 +1,19 @@
+// Framing of the host and sync protocols, free of any IO. The codecs work with
+// `tokio_util::codec::Framed` or can be fed byte buffers directly.
+// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/protocol.txt
+use crate::AdbTransportError;
+
+mod host;
+mod sync;
+
+pub use host::{HexCodec, HostCodec, HostReply};
+pub use sync::{SyncCodec, SyncRequest, SyncResponse, SYNC_DATA_MAX};
+
+/// How many bytes a decoder needs in total before it can yield the next frame.
+/// Lets a caller reading straight from a socket fetch exactly one frame and leave
+/// whatever follows it, e.g. the output of a shell after its OKAY, unread.
+/// Fails when the announced length is more than the protocol allows, before anything
+/// is allocated for it.
+pub trait FrameLength {
+    fn frame_len(&self, src: &[u8]) -> Result<usize, AdbTransportError>;
+}
@@ -0,0 +1,460 @@
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use super::FrameLength;
+use crate::result::dent::{SyncDent, SyncDentV2};
+use crate::result::stat::{StatInfo, StatV2};
+use crate::AdbTransportError;
+
+// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/file_sync_protocol.h
+const ID_LSTAT_V1: &[u8; 4] = b"STAT";
+const ID_STAT_V2: &[u8; 4] = b"STA2";
+const ID_LSTAT_V2: &[u8; 4] = b"LST2";
+const ID_LIST_V1: &[u8; 4] = b"LIST";
+const ID_LIST_V2: &[u8; 4] = b"LIS2";
+const ID_DENT_V1: &[u8; 4] = b"DENT";
+const ID_DENT_V2: &[u8; 4] = b"DNT2";
+const ID_SEND_V1: &[u8; 4] = b"SEND";
+const ID_SEND_V2: &[u8; 4] = b"SND2";
+const ID_RECV_V1: &[u8; 4] = b"RECV";
+const ID_RECV_V2: &[u8; 4] = b"RCV2";
+const ID_DONE: &[u8; 4] = b"DONE";
+const ID_DATA: &[u8; 4] = b"DATA";
+const ID_OKAY: &[u8; 4] = b"OKAY";
+const ID_FAIL: &[u8; 4] = b"FAIL";
+const ID_QUIT: &[u8; 4] = b"QUIT";
+
+/// Size of the v1 stat reply and of the v1 dent header, id included.
+const STAT_V1_LEN: usize = 16;
+const DENT_V1_LEN: usize = 20;
+/// Size of the v2 stat reply and of the v2 dent header, id included.
+const STAT_V2_LEN: usize = 72;
+const DENT_V2_LEN: usize = 76;
+/// Size of the DATA and DONE headers of a transfer and of the status after a SEND.
+const HEADER_LEN: usize = 8;
+/// Largest DATA payload, adbd never sends or accepts more in one frame.
+pub const SYNC_DATA_MAX: usize = 64 * 1024;
+/// Largest file name in a dent and largest FAIL message. Messages quote a path of up
+/// to 1024 bytes, so they get some room on top.
+const NAME_MAX: usize = 1024;
+const MESSAGE_MAX: usize = 4 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncRequest {
+    /// lstat(2) of a path, v1 only reports mode, size and mtime.
+    Stat(String),
+    StatV2(String),
+    LstatV2(String),
+    List(String),
+    ListV2(String),
+    /// Starts an upload to "<path>,<mode>".
+    Send(String),
+    SendV2 {
+        path: String,
+        mode: u32,
+        flags: u32,
+    },
+    Recv(String),
+    RecvV2 {
+        path: String,
+        flags: u32,
+    },
+    /// A chunk of an upload, at most 64KiB.
+    Data(Bytes),
+    /// Ends an upload, setting the modification time of the file.
+    Done(u32),
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncResponse {
+    Stat(StatInfo),
+    StatV2(StatV2),
+    Dent(SyncDent),
+    DentV2(SyncDentV2),
+    Data(Bytes),
+    /// The end of a listing or a download.
+    Done,
+    /// An upload was written.
+    Okay,
+    Fail(String),
+}
+
+impl SyncResponse {
+    /// The error for a reply that does not answer the request, a FAIL included.
+    pub fn into_error(self, context: &str) -> AdbTransportError {
+        match self {
+            SyncResponse::Fail(message) => AdbTransportError::from_fail(&message),
+            other => AdbTransportError::InvalidResponse(
+                context.to_string(),
+                Some(format!("{:?}", other)),
+            ),
+        }
+    }
+}
+
+/// What the last request expects back. The sync replies carry no length of their
+/// own, so e.g. a DONE is 20 bytes after a LIST but 8 bytes after a RECV.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Pending {
+    #[default]
+    Nothing,
+    Stat,
+    StatV2,
+    List,
+    ListV2,
+    Recv,
+    Send,
+}
+
+/// Encodes sync requests and decodes the replies to the last one encoded.
+#[derive(Debug, Clone, Default)]
+pub struct SyncCodec {
+    pending: Pending,
+}
+impl FrameLength for SyncCodec {
+    fn frame_len(&self, src: &[u8]) -> Result<usize, AdbTransportError> {
+        let Some(id) = src.get(..4) else {
+            return Ok(4);
+        };
+        // A length from the wire, refused above `max` rather than allocated.
+        let length_at = |offset: usize, max: usize| {
+            let Some(bytes) = src.get(offset..offset + 4) else {
+                return Ok(0);
+            };
+            let length = u32::from_le_bytes(bytes.try_into().unwrap()) as usize;
+            if length > max {
+                return Err(AdbTransportError::InvalidResponse(
+                    format!("sync {}", String::from_utf8_lossy(id)),
+                    Some(format!("length {} over {}", length, max)),
+                ));
+            }
+            Ok(length)
+        };
+        Ok(match (self.pending, id) {
+            (_, id) if id == ID_FAIL => HEADER_LEN + length_at(4, MESSAGE_MAX)?,
+            (Pending::Stat, _) => STAT_V1_LEN,
+            (Pending::StatV2, _) => STAT_V2_LEN,
+            (Pending::List, id) if id == ID_DENT_V1 => {
+                DENT_V1_LEN + length_at(DENT_V1_LEN - 4, NAME_MAX)?
+            }
+            (Pending::List, _) => DENT_V1_LEN,
+            (Pending::ListV2, id) if id == ID_DENT_V2 => {
+                DENT_V2_LEN + length_at(DENT_V2_LEN - 4, NAME_MAX)?
+            }
+            (Pending::ListV2, _) => DENT_V2_LEN,
+            (Pending::Recv, id) if id == ID_DATA => HEADER_LEN + length_at(4, SYNC_DATA_MAX)?,
+            _ => HEADER_LEN,
+        })
+    }
+}
+impl Decoder for SyncCodec {
+    type Item = SyncResponse;
+    type Error = AdbTransportError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let length = self.frame_len(src)?;
+        if src.len() < length {
+            src.reserve(length - src.len());
+            return Ok(None);
+        }
+        let mut frame = src.split_to(length);
+        let id: [u8; 4] = frame[..4].try_into()?;
+        frame.advance(4);
+        let response = match (self.pending, &id) {
+            (_, ID_FAIL) => {
+                frame.advance(4);
+                SyncResponse::Fail(String::from_utf8_lossy(&frame).to_string())
+            }
+            (Pending::Stat, ID_LSTAT_V1) => SyncResponse::Stat(StatInfo {
+                mode: frame.get_u32_le(),
+                size: frame.get_u32_le(),
+                mtime: frame.get_u32_le(),
+            }),
+            (Pending::StatV2, ID_STAT_V2 | ID_LSTAT_V2) => {
+                SyncResponse::StatV2(get_stat_v2(&mut frame))
+            }
+            (Pending::List, ID_DENT_V1) => {
+                let mode = frame.get_u32_le();
+                let size = frame.get_u32_le();
+                let mtime = frame.get_u32_le();
+                frame.advance(4);
+                SyncResponse::Dent(SyncDent {
+                    mode,
+                    size,
+                    mtime,
+                    name: String::from_utf8(frame.to_vec())?,
+                })
+            }
+            (Pending::ListV2, ID_DENT_V2) => {
+                let stat = get_stat_v2(&mut frame);
+                frame.advance(4);
+                SyncResponse::DentV2(SyncDentV2 {
+                    stat,
+                    name: String::from_utf8(frame.to_vec())?,
+                })
+            }
+            (Pending::Recv, ID_DATA) => {
+                frame.advance(4);
+                SyncResponse::Data(frame.freeze())
+            }
+            (Pending::List | Pending::ListV2 | Pending::Recv, ID_DONE) => SyncResponse::Done,
+            (Pending::Send, ID_OKAY) => SyncResponse::Okay,
+            _ => {
+                return Err(AdbTransportError::InvalidResponse(
+                    format!("sync {:?}", self.pending),
+                    Some(String::from_utf8_lossy(&id).to_string()),
+                ))
+            }
+        };
+        Ok(Some(response))
+    }
+}
+impl Encoder<SyncRequest> for SyncCodec {
+    type Error = AdbTransportError;
+
+    fn encode(&mut self, item: SyncRequest, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        match item {
+            SyncRequest::Stat(path) => {
+                self.pending = Pending::Stat;
+                put_path(dst, ID_LSTAT_V1, &path)?;
+            }
+            SyncRequest::StatV2(path) => {
+                self.pending = Pending::StatV2;
+                put_path(dst, ID_STAT_V2, &path)?;
+            }
+            SyncRequest::LstatV2(path) => {
+                self.pending = Pending::StatV2;
+                put_path(dst, ID_LSTAT_V2, &path)?;
+            }
+            SyncRequest::List(path) => {
+                self.pending = Pending::List;
+                put_path(dst, ID_LIST_V1, &path)?;
+            }
+            SyncRequest::ListV2(path) => {
+                self.pending = Pending::ListV2;
+                put_path(dst, ID_LIST_V2, &path)?;
+            }
+            SyncRequest::Send(path) => {
+                self.pending = Pending::Send;
+                put_path(dst, ID_SEND_V1, &path)?;
+            }
+            SyncRequest::SendV2 { path, mode, flags } => {
+                self.pending = Pending::Send;
+                put_path(dst, ID_SEND_V2, &path)?;
+                dst.put_slice(ID_SEND_V2);
+                dst.put_u32_le(mode);
+                dst.put_u32_le(flags);
+            }
+            SyncRequest::Recv(path) => {
+                self.pending = Pending::Recv;
+                put_path(dst, ID_RECV_V1, &path)?;
+            }
+            SyncRequest::RecvV2 { path, flags } => {
+                self.pending = Pending::Recv;
+                put_path(dst, ID_RECV_V2, &path)?;
+                dst.put_slice(ID_RECV_V2);
+                dst.put_u32_le(flags);
+            }
+            SyncRequest::Data(data) => {
+                dst.reserve(HEADER_LEN + data.len());
+                dst.put_slice(ID_DATA);
+                dst.put_u32_le(data.len() as u32);
+                dst.put_slice(&data);
+            }
+            SyncRequest::Done(mtime) => {
+                dst.put_slice(ID_DONE);
+                dst.put_u32_le(mtime);
+            }
+            SyncRequest::Quit => {
+                self.pending = Pending::Nothing;
+                dst.put_slice(ID_QUIT);
+                dst.put_u32_le(0);
+            }
+        }
+        Ok(())
+    }
+}
+fn put_path(dst: &mut BytesMut, id: &[u8; 4], path: &str) -> Result<(), AdbTransportError> {
+    if path.is_empty() {
+        return Err(AdbTransportError::InvalidPath("Path is empty".into()));
+    }
+    if path.len() > 1024 {
+        return Err(AdbTransportError::InvalidPath("Too long Path".into()));
+    }
+    dst.reserve(HEADER_LEN + path.len());
+    dst.put_slice(id);
+    dst.put_u32_le(path.len() as u32);
+    dst.put_slice(path.as_bytes());
+    Ok(())
+}
+fn get_stat_v2(frame: &mut BytesMut) -> StatV2 {
+    StatV2 {
+        error: frame.get_u32_le(),
+        dev: frame.get_u64_le(),
+        ino: frame.get_u64_le(),
+        mode: frame.get_u32_le(),
+        nlink: frame.get_u32_le(),
+        uid: frame.get_u32_le(),
+        gid: frame.get_u32_le(),
+        size: frame.get_u64_le(),
+        atime: frame.get_i64_le(),
+        mtime: frame.get_i64_le(),
+        ctime: frame.get_i64_le(),
+    }
+}
+
+#[cfg(test)]
+fn sync_frame(id: &[u8; 4], fields: &[u32], tail: &[u8]) -> BytesMut {
+    let mut frame = BytesMut::from(&id[..]);
+    fields.iter().for_each(|field| frame.put_u32_le(*field));
+    frame.put_slice(tail);
+    frame
+}
+#[test]
+fn test_sync_requests() {
+    let mut codec = SyncCodec::default();
+    let mut buffer = BytesMut::new();
+    codec
+        .encode(SyncRequest::Send("/sdcard/a,0644".to_string()), &mut buffer)
+        .unwrap();
+    codec
+        .encode(SyncRequest::Data(Bytes::from_static(b"abc")), &mut buffer)
+        .unwrap();
+    codec.encode(SyncRequest::Done(7), &mut buffer).unwrap();
+    assert_eq!(
+        &buffer[..],
+        &b"SEND\x0e\0\0\0/sdcard/a,0644DATA\x03\0\0\0abcDONE\x07\0\0\0"[..]
+    );
+    let mut buffer = BytesMut::new();
+    codec
+        .encode(
+            SyncRequest::RecvV2 {
+                path: "/a".to_string(),
+                flags: 0,
+            },
+            &mut buffer,
+        )
+        .unwrap();
+    assert_eq!(&buffer[..], &b"RCV2\x02\0\0\0/aRCV2\0\0\0\0"[..]);
+    assert!(codec
+        .encode(SyncRequest::Stat(String::new()), &mut BytesMut::new())
+        .is_err());
+}
+#[test]
+fn test_sync_stat() {
+    let mut codec = SyncCodec::default();
+    codec
+        .encode(SyncRequest::Stat("/a".to_string()), &mut BytesMut::new())
+        .unwrap();
+    let mut buffer = sync_frame(b"STAT", &[0o100644, 3], &[]);
+    assert_eq!(codec.frame_len(&buffer).unwrap(), 16);
+    assert_eq!(codec.decode(&mut buffer).unwrap(), None);
+    buffer.put_u32_le(9);
+    assert_eq!(
+        codec.decode(&mut buffer).unwrap(),
+        Some(SyncResponse::Stat(StatInfo {
+            mode: 0o100644,
+            size: 3,
+            mtime: 9
+        }))
+    );
+
+    codec
+        .encode(SyncRequest::LstatV2("/a".to_string()), &mut BytesMut::new())
+        .unwrap();
+    let mut buffer = sync_frame(b"LST2", &[0, 1, 0, 2, 0, 0o40755, 2, 1000, 1000], &[]);
+    buffer.put_u64_le(4096);
+    buffer.put_i64_le(1);
+    buffer.put_i64_le(2);
+    buffer.put_i64_le(3);
+    assert_eq!(codec.frame_len(&buffer).unwrap(), 72);
+    let Some(SyncResponse::StatV2(stat)) = codec.decode(&mut buffer).unwrap() else {
+        panic!("expected a v2 stat");
+    };
+    assert_eq!(
+        (stat.dev, stat.ino, stat.mode, stat.uid),
+        (1, 2, 0o40755, 1000)
+    );
+    assert_eq!(
+        (stat.size, stat.atime, stat.mtime, stat.ctime),
+        (4096, 1, 2, 3)
+    );
+}
+#[test]
+fn test_sync_list() {
+    let mut codec = SyncCodec::default();
+    codec
+        .encode(
+            SyncRequest::List("/sdcard".to_string()),
+            &mut BytesMut::new(),
+        )
+        .unwrap();
+    let mut buffer = sync_frame(b"DENT", &[0o40771, 4096, 5, 8], b"Download");
+    buffer.extend_from_slice(&sync_frame(b"DONE", &[0, 0, 0, 0], &[]));
+    buffer.extend_from_slice(b"next");
+    assert_eq!(codec.frame_len(&buffer).unwrap(), 28);
+    assert_eq!(
+        codec.decode(&mut buffer).unwrap(),
+        Some(SyncResponse::Dent(SyncDent {
+            mode: 0o40771,
+            size: 4096,
+            mtime: 5,
+            name: "Download".to_string()
+        }))
+    );
+    assert_eq!(codec.decode(&mut buffer).unwrap(), Some(SyncResponse::Done));
+    assert_eq!(&buffer[..], b"next");
+}
+#[test]
+fn test_sync_recv() {
+    let mut codec = SyncCodec::default();
+    codec
+        .encode(SyncRequest::Recv("/a".to_string()), &mut BytesMut::new())
+        .unwrap();
+    let mut buffer = sync_frame(b"DATA", &[3], b"abc");
+    buffer.extend_from_slice(&sync_frame(b"DONE", &[0], &[]));
+    assert_eq!(
+        codec.decode(&mut buffer).unwrap(),
+        Some(SyncResponse::Data(Bytes::from_static(b"abc")))
+    );
+    assert_eq!(codec.decode(&mut buffer).unwrap(), Some(SyncResponse::Done));
+
+    let message = b"open failed: No such file or directory";
+    let mut buffer = sync_frame(b"FAIL", &[message.len() as u32], message);
+    assert_eq!(
+        codec.decode(&mut buffer).unwrap(),
+        Some(SyncResponse::Fail(
+            String::from_utf8_lossy(message).to_string()
+        ))
+    );
+    assert!(codec.decode(&mut sync_frame(b"OKAY", &[0], &[])).is_err());
+}
+#[test]
+fn test_sync_oversized_frames() {
+    let mut codec = SyncCodec::default();
+    codec
+        .encode(SyncRequest::Recv("/a".to_string()), &mut BytesMut::new())
+        .unwrap();
+    // A header announcing 4GiB is refused before anything is allocated for it.
+    let mut buffer = sync_frame(b"DATA", &[u32::MAX], &[]);
+    assert!(matches!(
+        codec.frame_len(&buffer),
+        Err(AdbTransportError::InvalidResponse(..))
+    ));
+    assert!(codec.decode(&mut buffer).is_err());
+    let mut buffer = sync_frame(b"DATA", &[SYNC_DATA_MAX as u32 + 1], &[]);
+    assert!(codec.decode(&mut buffer).is_err());
+    let buffer = sync_frame(b"DATA", &[SYNC_DATA_MAX as u32], &[]);
+    assert_eq!(codec.frame_len(&buffer).unwrap(), 8 + SYNC_DATA_MAX);
+    assert!(codec
+        .frame_len(&sync_frame(b"FAIL", &[1 << 20], &[]))
+        .is_err());
+
+    codec
+        .encode(SyncRequest::List("/a".to_string()), &mut BytesMut::new())
+        .unwrap();
+    assert!(codec
+        .frame_len(&sync_frame(b"DENT", &[0, 0, 0, 1 << 20], &[]))
+        .is_err());
+}
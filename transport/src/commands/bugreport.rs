@@ -0,0 +1,112 @@
+use std::future::Future;
+
+use crate::commands::shell::shell_quote;
+use crate::result::bugreport::BugreportEvent;
+use crate::{AdbCommand, AdbTransportError, AdbTransports, DeviceSelector};
+use crate::Result;
+impl AdbTransports {
+    /// Captures a full bugreport with `bugreportz -p`, reporting its progress, then pulls
+    /// the zip into `output` and deletes it from the device. Returns the device-side path.
+    /// Fails with `Cancelled` when `cancel` resolves first.
+    #[async_backtrace::framed]
+    pub async fn bugreport(
+        &mut self,
+        selector: DeviceSelector,
+        output: &mut (dyn std::io::Write + Send),
+        mut progress: impl FnMut(&BugreportEvent),
+        cancel: impl Future<Output = ()>,
+    ) -> Result<String> {
+        tokio::pin!(cancel);
+        self.open_service(
+            selector.clone(),
+            AdbCommand::ShellExec("bugreportz -p".to_string()),
+        )
+        .await?;
+        let mut zip = None;
+        let mut line = Vec::new();
+        let mut buffer = [0u8; 1024];
+        loop {
+            let read = tokio::select! {
+                _ = &mut cancel => {
+                    self.new_connection().await?;
+                    return Err(AdbTransportError::Cancelled);
+                }
+                read = self.transports.read_(&mut buffer) => read?,
+            };
+            // A final line without a newline still counts.
+            let chunk: &[u8] = if read == 0 { b"\n" } else { &buffer[..read] };
+            for &byte in chunk {
+                if byte != b'\n' {
+                    line.push(byte);
+                    continue;
+                }
+                if let Some(event) = BugreportEvent::parse(&String::from_utf8_lossy(&line)) {
+                    progress(&event);
+                    match event {
+                        BugreportEvent::Ok(path) => zip = Some(path),
+                        BugreportEvent::Fail(message) => {
+                            self.new_connection().await?;
+                            return Err(AdbTransportError::AdbError(message));
+                        }
+                        _ => {}
+                    }
+                }
+                line.clear();
+            }
+            if read == 0 {
+                break;
+            }
+        }
+        self.new_connection().await?;
+        let zip = zip.ok_or_else(|| {
+            AdbTransportError::InvalidResponse(
+                "bugreport".to_string(),
+                Some("no OK line".to_string()),
+            )
+        })?;
+        self.pull(selector.clone(), &zip, output, cancel).await?;
+        self.new_connection().await?;
+        self.shell_output(selector, format!("rm -f {}", shell_quote(&zip)))
+            .await?;
+        Ok(zip)
+    }
+}
+
+#[tokio::test]
+async fn test_bugreport_cancel() {
+    use crate::transport::stream_transport::FakeServer;
+    use crate::SessionState;
+    use tokio::io::AsyncWriteExt;
+
+    // bugreportz announces the zip, then takes its time.
+    let server = FakeServer::new(|_| b"OKAY".to_vec()).with_service(|_, mut stream| {
+        Box::pin(async move {
+            stream
+                .write_all(b"BEGIN:/bugreports/bugreport.zip\n")
+                .await
+                .unwrap();
+            std::future::pending::<()>().await
+        })
+    });
+    let log = server.log.clone();
+    let mut adb = AdbTransports::with_connector(server).await.unwrap();
+    let mut events = Vec::new();
+    let cancel = tokio::time::sleep(std::time::Duration::from_millis(50));
+    let result = adb
+        .bugreport(
+            DeviceSelector::Any,
+            &mut Vec::new(),
+            |event| events.push(event.clone()),
+            cancel,
+        )
+        .await;
+    assert!(matches!(result, Err(AdbTransportError::Cancelled)));
+    assert_eq!(
+        events,
+        [BugreportEvent::Begin(
+            "/bugreports/bugreport.zip".to_string()
+        )]
+    );
+    assert_eq!(adb.state(), &SessionState::Idle);
+    assert_eq!(log.lock().unwrap().last().unwrap(), "connect");
+}
@@ -0,0 +1,37 @@
+use crate::result::connect::{ConnectOutcome, DisconnectOutcome, PairOutcome};
+use crate::{AdbCommand, AdbTransports};
+use crate::Result;
+const DEFAULT_TCPIP_PORT: u16 = 5555;
+impl AdbTransports {
+    /// Connects the server to a device over TCP/IP, `address` defaults to port 5555.
+    #[async_backtrace::framed]
+    pub async fn connect<A: AsRef<str>>(&self, address: A) -> Result<ConnectOutcome> {
+        let address = address.as_ref();
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", address, DEFAULT_TCPIP_PORT)
+        };
+        let reply = self.host_request(AdbCommand::Connect(address)).await?;
+        Ok(ConnectOutcome::from(reply.as_str()))
+    }
+    /// Disconnects one TCP/IP device, or all of them when `address` is `None`.
+    #[async_backtrace::framed]
+    pub async fn disconnect<A: AsRef<str>>(&self, address: Option<A>) -> Result<DisconnectOutcome> {
+        let address = address.map(|address| address.as_ref().to_string());
+        let reply = self.host_request(AdbCommand::Disconnect(address)).await?;
+        Ok(DisconnectOutcome::from(reply.as_str()))
+    }
+    /// Pairs with an Android 11+ device using the code shown in its wireless debugging
+    /// settings. `address` is the pairing address, not the one used to connect.
+    #[async_backtrace::framed]
+    pub async fn pair<C: AsRef<str>, A: AsRef<str>>(
+        &self,
+        code: C,
+        address: A,
+    ) -> Result<PairOutcome> {
+        let command = AdbCommand::Pair(code.as_ref().to_string(), address.as_ref().to_string());
+        let reply = self.host_request(command).await?;
+        Ok(PairOutcome::from(reply.as_str()))
+    }
+}
@@ -1,49 +1,98 @@
-use crate::{AdbTransports, result::device::Devices, AdbTransportError};
-use anyhow::Result;
+use crate::result::track::{diff_devices, DeviceEvent};
+use crate::codec::HexCodec;
+use crate::transport::transport::{read_frame, AdbTransport};
+use crate::{AdbCommand, AdbTransports};
+use crate::Result;
 use crate::result::device::Device;
+use futures_util::{future, stream, Stream, StreamExt};
 impl AdbTransports {
-    pub async fn devices(&mut self)->Result<Devices>{
-        let resp = self.transports.send_command(crate::AdbCommand::Devices, true).await?;
-        if self.json{
-            let resp = String::from_utf8(resp).map_err(|err|AdbTransportError::AdbError(err.to_string()))?;
-            let mut devices: Vec<Device> = Vec::new();
-            resp.split("\n").for_each(|line|{
-                if let Ok(device) = Device::try_from(line){
-                    devices.push(device);
-                }
-            });
-             Ok(Devices::Devices(devices))
-        }else {
-            return Ok(Devices::Raw(String::from_utf8(resp).map_err(|err|AdbTransportError::AdbError(err.to_string()))?));
-        }
+    /// The connected devices, as `adb devices` lists them.
+    #[async_backtrace::framed]
+    pub async fn devices(&self) -> Result<Vec<Device>> {
+        Ok(Device::parse_list(&self.raw_devices(false).await?))
     }
-    pub async fn devices_long(&mut self)->Result<Devices>{
-        let resp = self.transports.send_command(crate::AdbCommand::DevicesLong, true).await?;
-        if self.json{
-                    let resp = String::from_utf8(resp).map_err(|err|AdbTransportError::AdbError(err.to_string()))?;
-            let mut devices: Vec<Device> = Vec::new();
-            resp.split("\n").for_each(|line|{
-                if let Ok(device) = Device::try_from(line){
-                    devices.push(device);
-                }
-            });
-             Ok(Devices::Devices(devices))
-        }else {
-            return Ok(Devices::Raw(String::from_utf8(resp).map_err(|err|AdbTransportError::AdbError(err.to_string()))?));
-        }
+    /// The connected devices with their product, model, device and transport id, as
+    /// `adb devices -l` lists them.
+    #[async_backtrace::framed]
+    pub async fn devices_long(&self) -> Result<Vec<Device>> {
+        Ok(Device::parse_list(&self.raw_devices(true).await?))
     }
-    pub async fn track_device(&mut self,callback: impl Fn(Device))->Result<()>{
-        
-        self.transports.send_command(crate::AdbCommand::TrackDevices, true).await?;
-        loop {
-            let length = self.transports.get_length().await?;
-            let message = self.transports.read_exact(length).await?;
-            let message = String::from_utf8(message).ok();
-            if let Some(message) = message {
-                if let Ok(device) = Device::try_from(message.as_str()){
-                    callback(device);
-                }
+    /// The device list exactly as the server sent it, for output `Device` does not model.
+    #[async_backtrace::framed]
+    pub async fn raw_devices(&self, long: bool) -> Result<String> {
+        let command = if long {
+            AdbCommand::DevicesLong
+        } else {
+            AdbCommand::Devices
+        };
+        self.host_request_raw(command).await
+    }
+    /// Tracks device connections, yielding an event for every change. The first events
+    /// describe the devices already connected. Tracking runs on its own connection and
+    /// stops when the stream is dropped.
+    #[async_backtrace::framed]
+    pub async fn track_devices(&self) -> Result<impl Stream<Item = Result<DeviceEvent>>> {
+        Ok(device_events(
+            self.track_snapshots(AdbCommand::TrackDevices).await?,
+        ))
+    }
+    /// Same as `track_devices`, with the product, model and transport id of each device.
+    #[async_backtrace::framed]
+    pub async fn track_devices_long(&self) -> Result<impl Stream<Item = Result<DeviceEvent>>> {
+        Ok(device_events(
+            self.track_snapshots(AdbCommand::TrackDevicesLong).await?,
+        ))
+    }
+    /// Yields the full device list every time the server reports a change.
+    pub(crate) async fn track_snapshots(
+        &self,
+        command: AdbCommand,
+    ) -> Result<impl Stream<Item = Result<Vec<Device>>>> {
+        let mut transport = self.transports.duplicate().await?;
+        transport.send_command(command, false).await?;
+        Ok(stream::unfold(Some(transport), |transport| async move {
+            let mut transport = transport?;
+            match read_snapshot(transport.as_mut()).await {
+                Ok(devices) => Some((Ok(devices), Some(transport))),
+                // The connection is unusable after a failed read, end the stream.
+                Err(err) => Some((Err(err), None)),
             }
-        }
+        }))
     }
-}
\ No newline at end of file
+}
+pub(crate) async fn read_snapshot(transport: &mut dyn AdbTransport) -> Result<Vec<Device>> {
+    let message = read_frame(transport, &mut HexCodec).await?;
+    Ok(Device::parse_list(&String::from_utf8_lossy(&message)))
+}
+fn device_events(
+    snapshots: impl Stream<Item = Result<Vec<Device>>>,
+) -> impl Stream<Item = Result<DeviceEvent>> {
+    snapshots
+        .scan(Vec::new(), |known: &mut Vec<Device>, snapshot| {
+            let events = match snapshot {
+                Ok(devices) => {
+                    let events = diff_devices(known, &devices);
+                    *known = devices;
+                    events.into_iter().map(Ok).collect()
+                }
+                Err(err) => vec![Err(err)],
+            };
+            future::ready(Some(stream::iter(events)))
+        })
+        .flatten()
+}
+
+#[tokio::test]
+async fn test_raw_devices() {
+    use crate::transport::stream_transport::FakeServer;
+
+    let server = FakeServer::new(|request| match request {
+        "host:devices" => b"OKAY0015emulator-5554\tdevice\n".to_vec(),
+        _ => b"FAIL0014unknown host service".to_vec(),
+    });
+    let adb = AdbTransports::with_connector(server).await.unwrap();
+    assert_eq!(
+        adb.raw_devices(false).await.unwrap(),
+        "emulator-5554\tdevice\n"
+    );
+}
@@ -0,0 +1,60 @@
+use crate::options::features::FeatureDetection;
+use crate::result::device::DeviceState;
+use crate::{AdbCommand, AdbTransportError, AdbTransports, DeviceSelector, HostQuery};
+use crate::Result;
+impl AdbTransports {
+    #[async_backtrace::framed]
+    pub async fn get_state(&self, selector: DeviceSelector) -> Result<DeviceState> {
+        let state = self.host_query(selector, HostQuery::GetState).await?;
+        let (_, state) = DeviceState::parse_device_state(state.trim()).map_err(|err| {
+            AdbTransportError::InvalidResponse("get_state".to_string(), Some(err.to_string()))
+        })?;
+        Ok(state)
+    }
+    #[async_backtrace::framed]
+    pub async fn get_serialno(&self, selector: DeviceSelector) -> Result<String> {
+        self.host_query(selector, HostQuery::GetSerialNo).await
+    }
+    /// Returns `None` when the server does not know the device path, e.g. for emulators.
+    #[async_backtrace::framed]
+    pub async fn get_devpath(&self, selector: DeviceSelector) -> Result<Option<String>> {
+        let devpath = self.host_query(selector, HostQuery::GetDevPath).await?;
+        if devpath.is_empty() || devpath == "unknown" {
+            return Ok(None);
+        }
+        Ok(Some(devpath))
+    }
+    #[async_backtrace::framed]
+    pub async fn features(&self, selector: DeviceSelector) -> Result<Vec<String>> {
+        let features = self.host_query(selector, HostQuery::Features).await?;
+        Ok(features
+            .split(',')
+            .filter(|feature| !feature.is_empty())
+            .map(String::from)
+            .collect())
+    }
+    /// Whether the device has `feature`, going by the feature-detection mode.
+    pub(crate) async fn has_feature(&self, selector: &DeviceSelector, feature: &str) -> Result<bool> {
+        let features = match &self.features {
+            FeatureDetection::Legacy => return Ok(false),
+            FeatureDetection::Query => self.features(selector.clone()).await?,
+            FeatureDetection::Assume(features) => features.clone(),
+        };
+        Ok(features.iter().any(|known| known == feature))
+    }
+    /// Runs a host query on its own connection, the session's device selection is kept.
+    async fn host_query(&self, selector: DeviceSelector, query: HostQuery) -> Result<String> {
+        self.host_request(AdbCommand::HostQuery(selector, query))
+            .await
+    }
+    /// Sends a host service request on a fresh connection and returns its reply.
+    pub(crate) async fn host_request(&self, command: AdbCommand) -> Result<String> {
+        Ok(self.host_request_raw(command).await?.trim().to_string())
+    }
+    /// Same as `host_request`, with the reply's surrounding whitespace kept.
+    pub(crate) async fn host_request_raw(&self, command: AdbCommand) -> Result<String> {
+        let mut transport = self.transports.duplicate().await?;
+        let resp = transport.send_command(command, true).await?;
+        String::from_utf8(resp).map_err(|err| AdbTransportError::ConversionError(err.to_string()))
+    }
+}
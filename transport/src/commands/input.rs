@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use crate::options::input::{InputBatch, InputEvent, KeyCode};
+use crate::{AdbTransportError, AdbTransports, DeviceSelector};
+use crate::Result;
+impl AdbTransports {
+    #[async_backtrace::framed]
+    pub async fn input(&mut self, selector: DeviceSelector, event: InputEvent) -> Result<()> {
+        self.input_command(selector, event.to_command()).await
+    }
+    /// Sends every event of `batch` in one shell invocation.
+    #[async_backtrace::framed]
+    pub async fn input_batch(
+        &mut self,
+        selector: DeviceSelector,
+        batch: &InputBatch,
+    ) -> Result<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+        self.input_command(selector, batch.to_command()).await
+    }
+    pub async fn tap(&mut self, selector: DeviceSelector, x: u32, y: u32) -> Result<()> {
+        self.input(selector, InputEvent::Tap { x, y }).await
+    }
+    pub async fn swipe(
+        &mut self,
+        selector: DeviceSelector,
+        from: (u32, u32),
+        to: (u32, u32),
+        duration: Option<Duration>,
+    ) -> Result<()> {
+        self.input(selector, InputEvent::Swipe { from, to, duration })
+            .await
+    }
+    pub async fn keyevent(&mut self, selector: DeviceSelector, code: KeyCode) -> Result<()> {
+        self.input(
+            selector,
+            InputEvent::KeyEvent {
+                code,
+                long_press: false,
+            },
+        )
+        .await
+    }
+    pub async fn long_press(
+        &mut self,
+        selector: DeviceSelector,
+        x: u32,
+        y: u32,
+        duration: Duration,
+    ) -> Result<()> {
+        self.input(selector, InputEvent::LongPress { x, y, duration })
+            .await
+    }
+    pub async fn text<T: ToString>(&mut self, selector: DeviceSelector, text: T) -> Result<()> {
+        self.input(selector, InputEvent::Text(text.to_string()))
+            .await
+    }
+    #[async_backtrace::framed]
+    async fn input_command(&mut self, selector: DeviceSelector, cmd: String) -> Result<()> {
+        let output = self.shell_output(selector, cmd).await?;
+        // `input` reports failures on the shell output rather than through its exit code.
+        let output = String::from_utf8_lossy(&output);
+        if output.starts_with("Error") || output.contains("Exception") {
+            return Err(AdbTransportError::AdbError(output.trim().to_string()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::Path;
+
+use tokio::io::AsyncReadExt;
+
+use crate::commands::shell::shell_quote;
+use crate::result::install::install_failure;
+use crate::Result;
+use crate::{AdbCommand, AdbTransportError, AdbTransports, DeviceSelector};
+// Devices with `cmd` stream the APK to the package manager, older ones need a copy first.
+const CMD: &str = "cmd";
+const STAGING_DIR: &str = "/data/local/tmp";
+impl AdbTransports {
+    /// Installs the APK at `apk`, replacing an installed version of the package.
+    #[async_backtrace::framed]
+    pub async fn install<P: AsRef<Path>>(
+        &mut self,
+        selector: DeviceSelector,
+        apk: P,
+    ) -> Result<()> {
+        let apk = apk.as_ref();
+        let output = if self.has_feature(&selector, CMD).await? {
+            self.install_streamed(selector, apk).await?
+        } else {
+            self.install_pushed(selector, apk).await?
+        };
+        match install_failure(&String::from_utf8_lossy(&output)) {
+            Some(reason) => Err(AdbTransportError::InstallFailed(reason)),
+            None => Ok(()),
+        }
+    }
+    async fn install_streamed(&mut self, selector: DeviceSelector, apk: &Path) -> Result<Vec<u8>> {
+        let mut file = tokio::fs::File::open(apk).await?;
+        let size = file.metadata().await?.len();
+        let cmd = format!("cmd package install -r -S {}", size);
+        self.open_service(selector, AdbCommand::Exec(cmd)).await?;
+        let mut buffer = vec![0u8; self.buffer_size];
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            self.transports.write_all(&buffer[..read]).await?;
+        }
+        // The package manager answers once it has read `size` bytes.
+        self.transports.read_to_end().await
+    }
+    async fn install_pushed(&mut self, selector: DeviceSelector, apk: &Path) -> Result<Vec<u8>> {
+        let name = apk
+            .file_name()
+            .and_then(|name| name.to_str())
+            .ok_or_else(|| AdbTransportError::InvalidPath(apk.display().to_string()))?;
+        let remote = format!("{}/{}", STAGING_DIR, name);
+        let mut file = std::fs::File::open(apk)?;
+        self.push(selector.clone(), &mut file, &remote, std::future::pending())
+            .await?;
+        let output = self
+            .shell_output(
+                selector.clone(),
+                format!("pm install -r {}", shell_quote(&remote)),
+            )
+            .await;
+        self.shell_output(selector, format!("rm -f {}", shell_quote(&remote)))
+            .await?;
+        output
+    }
+}
+
+#[cfg(test)]
+fn test_apk(name: &str, content: &[u8]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("adb-install-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let apk = dir.join(name);
+    std::fs::write(&apk, content).unwrap();
+    apk
+}
+#[tokio::test]
+async fn test_install_streamed() {
+    use crate::options::features::FeatureDetection;
+    use crate::transport::stream_transport::FakeServer;
+    use tokio::io::AsyncWriteExt;
+
+    let server = FakeServer::new(|_| b"OKAY".to_vec()).with_service(|request, mut stream| {
+        Box::pin(async move {
+            let size = request
+                .strip_prefix("exec:cmd package install -r -S ")
+                .unwrap();
+            let mut apk = vec![0u8; size.parse().unwrap()];
+            stream.read_exact(&mut apk).await.unwrap();
+            let reply: &[u8] = match &apk[..] {
+                b"PK streamed" => b"Success\n",
+                _ => b"Failure [INSTALL_PARSE_FAILED_NOT_APK: not an apk]\n",
+            };
+            stream.write_all(reply).await.unwrap();
+        })
+    });
+    let log = server.log.clone();
+    let mut adb = AdbTransports::builder()
+        .connector(server)
+        .feature_detection(FeatureDetection::Assume(vec![CMD.to_string()]))
+        .build()
+        .await
+        .unwrap();
+    let apk = test_apk("streamed.apk", b"PK streamed");
+    adb.install(DeviceSelector::Any, &apk).await.unwrap();
+    assert!(log
+        .lock()
+        .unwrap()
+        .contains(&"exec:cmd package install -r -S 11".to_string()));
+
+    let apk = test_apk("broken.apk", b"nope");
+    assert!(matches!(
+        adb.install(DeviceSelector::Any, &apk).await,
+        Err(AdbTransportError::InstallFailed(reason))
+            if reason == "INSTALL_PARSE_FAILED_NOT_APK: not an apk"
+    ));
+}
+#[tokio::test]
+async fn test_install_pushed() {
+    use crate::transport::stream_transport::FakeServer;
+    use tokio::io::AsyncWriteExt;
+
+    let server = FakeServer::new(|_| b"OKAY".to_vec()).with_service(|request, mut stream| {
+        Box::pin(async move {
+            if request == "sync:" {
+                // SEND <path,mode>, DATA chunks, then DONE.
+                let mut header = [0u8; 8];
+                stream.read_exact(&mut header).await.unwrap();
+                let mut path =
+                    vec![0u8; u32::from_le_bytes(header[4..].try_into().unwrap()) as usize];
+                stream.read_exact(&mut path).await.unwrap();
+                let mut data = Vec::new();
+                loop {
+                    stream.read_exact(&mut header).await.unwrap();
+                    if &header[..4] == b"DONE" {
+                        break;
+                    }
+                    let mut chunk =
+                        vec![0u8; u32::from_le_bytes(header[4..].try_into().unwrap()) as usize];
+                    stream.read_exact(&mut chunk).await.unwrap();
+                    data.extend_from_slice(&chunk);
+                }
+                assert_eq!(data, b"PK pushed");
+                stream.write_all(b"OKAY\0\0\0\0").await.unwrap();
+            } else if request.starts_with("shell,raw:pm install") {
+                stream.write_all(b"Success\n").await.unwrap();
+            }
+        })
+    });
+    let log = server.log.clone();
+    let mut adb = AdbTransports::with_connector(server).await.unwrap();
+    // The name is quoted for the device shell, it cannot end the command early.
+    let apk = test_apk("it's; reboot.apk", b"PK pushed");
+    adb.install(DeviceSelector::Any, &apk).await.unwrap();
+    let log = log.lock().unwrap();
+    assert!(
+        log.contains(&"shell,raw:pm install -r '/data/local/tmp/it'\\''s; reboot.apk'".to_string())
+    );
+    assert!(log.contains(&"shell,raw:rm -f '/data/local/tmp/it'\\''s; reboot.apk'".to_string()));
+}
@@ -0,0 +1,178 @@
+use crate::result::jdwp::{parse_app_processes, parse_jdwp_pids, AppProcess};
+use crate::codec::HexCodec;
+use crate::transport::transport::{read_frame, AdbTransport};
+use crate::Result;
+use crate::{AdbCommand, AdbTransportError, AdbTransports, DeviceSelector};
+use futures_util::stream::FuturesUnordered;
+use futures_util::{stream, Stream, StreamExt};
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+impl AdbTransports {
+    /// Yields the pids of the debuggable processes every time the list changes.
+    /// Tracking runs on its own connection and stops when the stream is dropped.
+    #[async_backtrace::framed]
+    pub async fn track_jdwp(
+        &self,
+        selector: DeviceSelector,
+    ) -> Result<impl Stream<Item = Result<Vec<u32>>>> {
+        let transport = self
+            .device_connection(selector, AdbCommand::TrackJdwp)
+            .await?;
+        Ok(tracked_messages(transport, |message| {
+            Ok(parse_jdwp_pids(&String::from_utf8_lossy(&message)))
+        }))
+    }
+    /// Yields the debuggable and profileable processes every time the list changes,
+    /// with their package names and whether they wait for a debugger.
+    #[async_backtrace::framed]
+    pub async fn track_app(
+        &self,
+        selector: DeviceSelector,
+    ) -> Result<impl Stream<Item = Result<Vec<AppProcess>>>> {
+        let transport = self
+            .device_connection(selector, AdbCommand::TrackApp)
+            .await?;
+        Ok(tracked_messages(transport, |message| {
+            parse_app_processes(&message)
+        }))
+    }
+    /// Forwards debugger connections accepted on `listener` to the JDWP thread of `pid`,
+    /// like `adb forward tcp:8700 jdwp:<pid>`. Debuggers are served side by side, a failed
+    /// session only closes its own connection, and this runs until the listener fails.
+    #[async_backtrace::framed]
+    pub async fn forward_jdwp(
+        &self,
+        selector: DeviceSelector,
+        pid: u32,
+        listener: TcpListener,
+    ) -> Result<()> {
+        let mut sessions = FuturesUnordered::new();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => {
+                    let (debugger, _) = accepted?;
+                    sessions.push(self.debug_session(selector.clone(), pid, debugger));
+                }
+                // Dropping the debugger's stream is all a failed session needs.
+                Some(_) = sessions.next(), if !sessions.is_empty() => {}
+            }
+        }
+    }
+    async fn debug_session(
+        &self,
+        selector: DeviceSelector,
+        pid: u32,
+        mut debugger: TcpStream,
+    ) -> Result<()> {
+        let mut transport = self
+            .device_connection(selector, AdbCommand::Jdwp(pid))
+            .await?;
+        pump(transport.as_mut(), &mut debugger, self.buffer_size).await
+    }
+    /// Opens a new connection switched to the device and starts `command` on it.
+    async fn device_connection(
+        &self,
+        selector: DeviceSelector,
+        command: AdbCommand,
+    ) -> Result<Box<dyn AdbTransport>> {
+        let mut transport = self.transports.duplicate().await?;
+        transport
+            .send_command(selector.transport_command(), false)
+            .await?;
+        transport.send_command(command, false).await?;
+        Ok(transport)
+    }
+}
+/// Reads hex length prefixed messages until the connection fails.
+fn tracked_messages<T>(
+    transport: Box<dyn AdbTransport>,
+    parse: fn(Vec<u8>) -> Result<T>,
+) -> impl Stream<Item = Result<T>> {
+    stream::unfold(Some(transport), move |transport| async move {
+        let mut transport = transport?;
+        match read_frame(transport.as_mut(), &mut HexCodec).await {
+            Ok(message) => Some((parse(message.to_vec()), Some(transport))),
+            // The connection is unusable after a failed read, end the stream.
+            Err(err) => Some((Err(err), None)),
+        }
+    })
+}
+/// Copies bytes both ways until either side closes.
+async fn pump(
+    transport: &mut dyn AdbTransport,
+    debugger: &mut TcpStream,
+    buffer_size: usize,
+) -> Result<()> {
+    let mut from_device = vec![0u8; buffer_size];
+    let mut from_debugger = vec![0u8; buffer_size];
+    loop {
+        tokio::select! {
+            read = transport.read_(&mut from_device) => {
+                let read = read?;
+                if read == 0 {
+                    break;
+                }
+                debugger.write_all(&from_device[..read]).await?;
+            }
+            read = debugger.read(&mut from_debugger) => {
+                let read = read.map_err(AdbTransportError::IoError)?;
+                if read == 0 {
+                    break;
+                }
+                transport.write_all(&from_debugger[..read]).await?;
+            }
+        }
+    }
+    // The debugger may have closed its side already, the session still ended cleanly.
+    let _ = debugger.shutdown().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_forward_jdwp() {
+    use crate::transport::stream_transport::FakeServer;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // The first debugger finds the process gone, the others reach it.
+    static SESSIONS: AtomicUsize = AtomicUsize::new(0);
+    let server = FakeServer::new(|request| match request {
+        "jdwp:42" if SESSIONS.fetch_add(1, Ordering::SeqCst) == 0 => {
+            b"FAIL000fno such process".to_vec()
+        }
+        _ => b"OKAY".to_vec(),
+    })
+    .with_service(|_, mut stream| {
+        Box::pin(async move {
+            let mut buffer = [0u8; 64];
+            while let Ok(read @ 1..) = stream.read(&mut buffer).await {
+                stream.write_all(&buffer[..read]).await.unwrap();
+            }
+        })
+    });
+    let adb = AdbTransports::with_connector(server).await.unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let forward = adb.forward_jdwp(DeviceSelector::Any, 42, listener);
+    let debuggers = async {
+        let mut failed = TcpStream::connect(address).await.unwrap();
+        let mut buffer = [0u8; 14];
+        assert_eq!(failed.read(&mut buffer).await.unwrap(), 0);
+
+        // An idle debugger does not hold up the next one.
+        let mut idle = TcpStream::connect(address).await.unwrap();
+        let mut active = TcpStream::connect(address).await.unwrap();
+        active.write_all(b"JDWP-Handshake").await.unwrap();
+        active.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"JDWP-Handshake");
+        idle.write_all(b"JDWP-Handshake").await.unwrap();
+        idle.read_exact(&mut buffer).await.unwrap();
+        assert_eq!(&buffer, b"JDWP-Handshake");
+    };
+    tokio::select! {
+        result = forward => panic!("forwarding stopped: {:?}", result.err()),
+        served = tokio::time::timeout(std::time::Duration::from_secs(5), debuggers) => {
+            served.unwrap()
+        }
+    }
+}
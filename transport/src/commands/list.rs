@@ -1,46 +1,33 @@
-use crate::result::dent::{SyncDent, SyncDentV1};
-use crate::result::device::Device;
-use crate::result::stat::StatInfo;
-use crate::utils::check_path;
-use crate::{result::device::Devices, AdbCommand, AdbTransportError, AdbTransports};
-use anyhow::Result;
+use crate::codec::{SyncCodec, SyncRequest, SyncResponse};
+use crate::result::dent::SyncDent;
+use crate::transport::transport::{read_frame, write_frame};
+use crate::options::path::PathPolicy;
+use crate::{AdbTransports, DeviceSelector};
+use crate::Result;
 impl AdbTransports {
     #[async_backtrace::framed]
-    pub async fn list<S: ToString>(
+    pub async fn list(
         &mut self,
         path: String,
-        serial: Option<S>,
+        selector: DeviceSelector,
     ) -> Result<Vec<SyncDent>> {
-        //check path
-        let _ = check_path(path.clone())?;
-        self.may_set_serial(serial).await?;
-        self.may_set_sync().await?;
-        self.sync_list_(path).await
+        PathPolicy::validate(&path)?;
+        self.may_set_sync(selector).await?;
+        let result = self.sync_list_(path).await;
+        self.consume_on_error(result)
     }
     #[async_backtrace::framed]
     async fn sync_list_(&mut self, path: String) -> Result<Vec<SyncDent>> {
-        self.transports.send_sync_command(crate::AdbSyncModeCommand::List).await?;
-        // println!("path: {}", path);
-        let mut buf = Vec::new();
-        let path_length = (path.len() as u32).to_le_bytes(); // Convert path_length to bytes
-        buf.extend_from_slice(&path_length);
-        buf.extend_from_slice(path.as_bytes());
-        // println!("buf {:?}",buf);
-        self.transports.write_all(&buf).await?;
+        let mut codec = SyncCodec::default();
+        write_frame(self.transports.as_mut(), &mut codec, SyncRequest::List(path)).await?;
         let mut dents: Vec<SyncDent> = Vec::new();
         loop {
-            let msg = SyncDent::from_le_bytes_transport(self.transports.as_mut()).await;
-            match msg {
-                Ok(msg) => {
-                    dents.push(msg);
-                }
-                Err(AdbTransportError::EOF)=> {break;}
-                Err(err)=>{
-                    return Err(err.into())
-                }
+            match read_frame(self.transports.as_mut(), &mut codec).await? {
+                SyncResponse::Dent(dent) => dents.push(dent),
+                SyncResponse::Done => break,
+                response => return Err(response.into_error("sync_list")),
             }
         }
-        self.new_connection().await?;
         Ok(dents)
     }
 }
@@ -0,0 +1,75 @@
+use crate::result::connect::{ConnectOutcome, PairOutcome};
+use crate::result::mdns::{MdnsCheck, MdnsService, MdnsServiceType};
+use crate::Result;
+use crate::{AdbCommand, AdbTransportError, AdbTransports};
+impl AdbTransports {
+    /// Asks whether the server's mDNS discovery is running.
+    #[async_backtrace::framed]
+    pub async fn mdns_check(&self) -> Result<MdnsCheck> {
+        let reply = self.host_request(AdbCommand::MdnsCheck).await?;
+        Ok(MdnsCheck::from(reply.as_str()))
+    }
+    /// Lists the adb services the server discovered through mDNS.
+    #[async_backtrace::framed]
+    pub async fn mdns_services(&self) -> Result<Vec<MdnsService>> {
+        let reply = self.host_request(AdbCommand::MdnsServices).await?;
+        Ok(MdnsService::parse_list(&reply))
+    }
+    /// Connects to the device advertising `instance` as a connectable service.
+    #[async_backtrace::framed]
+    pub async fn connect_mdns<I: AsRef<str>>(&self, instance: I) -> Result<ConnectOutcome> {
+        let service = self
+            .find_mdns_service(instance.as_ref(), |service_type| {
+                matches!(
+                    service_type,
+                    MdnsServiceType::TlsConnect | MdnsServiceType::Adb
+                )
+            })
+            .await?;
+        self.connect(service.address.to_string()).await
+    }
+    /// Pairs with the device advertising `instance` as a pairing service.
+    #[async_backtrace::framed]
+    pub async fn pair_mdns<C: AsRef<str>, I: AsRef<str>>(
+        &self,
+        code: C,
+        instance: I,
+    ) -> Result<PairOutcome> {
+        let service = self
+            .find_mdns_service(instance.as_ref(), |service_type| {
+                *service_type == MdnsServiceType::TlsPairing
+            })
+            .await?;
+        self.pair(code, service.address.to_string()).await
+    }
+    async fn find_mdns_service(
+        &self,
+        instance: &str,
+        accept: impl Fn(&MdnsServiceType) -> bool,
+    ) -> Result<MdnsService> {
+        self.mdns_services()
+            .await?
+            .into_iter()
+            .find(|service| service.instance == instance && accept(&service.service_type))
+            .ok_or_else(|| AdbTransportError::MdnsServiceNotFound(instance.to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_connect_mdns_not_found() {
+    use crate::transport::stream_transport::FakeServer;
+
+    // The instance only advertises pairing, there is nothing to connect to.
+    let server = FakeServer::new(|request| match request {
+        "host:mdns:services" => {
+            let services = "phone\t_adb-tls-pairing._tcp\t192.168.1.20:40123\n";
+            format!("OKAY{:04x}{}", services.len(), services).into_bytes()
+        }
+        _ => b"FAIL0014unknown host service".to_vec(),
+    });
+    let adb = AdbTransports::with_connector(server).await.unwrap();
+    assert!(matches!(
+        adb.connect_mdns("phone").await,
+        Err(AdbTransportError::MdnsServiceNotFound(instance)) if instance == "phone"
+    ));
+}
@@ -1,31 +1,40 @@
+mod bugreport;
+mod connect;
 mod devices;
+mod host;
+mod input;
+mod install;
+mod jdwp;
 mod list;
+mod mdns;
 mod pull;
 mod push;
+mod screenrecord;
+pub(crate) mod service;
 mod shell;
 mod stat;
+mod tracker;
+mod wait;
 
 #[cfg(test)]
 mod command_test {
-    use crate::{result::stat::FileType, AdbTransports};
-    use anyhow::Result;
+    use crate::{result::stat::FileType, AdbTransports, DeviceSelector};
+    use crate::Result;
     async fn new_transport() -> Result<AdbTransports> {
-        Ok(AdbTransports::new("/var/run/adb.sock".to_string(), false).await?)
+        AdbTransports::new("/var/run/adb.sock".to_string()).await
     }
     #[tokio::test]
     #[async_backtrace::framed]
     async fn test_list() {
         let mut transport = new_transport().await.unwrap();
-        let serial: Option<String> = None;
-        let list = transport.list("/sdcard".to_string(), serial).await.unwrap();
+        let list = transport.list("/sdcard".to_string(), DeviceSelector::Any).await.unwrap();
         println!("{:?}", list);
     }
     #[tokio::test]
     async fn test_stat_non_exist() {
         let mut transport = new_transport().await.unwrap();
-        let serial: Option<String> = None;
         let stat = transport
-            .sync_stat("/sdcard/NON_EXITST_XXXXX".to_string(), serial)
+            .sync_stat("/sdcard/NON_EXITST_XXXXX".to_string(), DeviceSelector::Any)
             .await
             .unwrap();
         println!("{:?}", stat.get_file_type());
@@ -34,9 +43,8 @@ mod command_test {
     #[tokio::test]
     async fn test_stat_dic() {
         let mut transport = new_transport().await.unwrap();
-        let serial: Option<String> = None;
         let stat = transport
-            .sync_stat("/sdcard/Download".to_string(), serial)
+            .sync_stat("/sdcard/Download".to_string(), DeviceSelector::Any)
             .await
             .unwrap();
         println!("{:?}", stat.get_file_type());
@@ -45,9 +53,8 @@ mod command_test {
     #[tokio::test]
     async fn test_stat_file() {
         let mut transport = new_transport().await.unwrap();
-        let serial: Option<String> = None;
         let stat = transport
-            .sync_stat("/system/build.prop".to_string(), serial)
+            .sync_stat("/system/build.prop".to_string(), DeviceSelector::Any)
             .await
             .unwrap();
         println!("{:?}", stat.get_file_type());
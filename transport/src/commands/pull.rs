@@ -1,83 +1,94 @@
-use std::path::PathBuf;
+use std::future::Future;
 
-use crate::{AdbCommand, AdbTransportError, AdbTransports};
-use anyhow::Result;
-const SYNC_DATA_MAX: usize = 64 * 1024;
-const ID_DONE: u32 = u32::from_le_bytes([b'D', b'O', b'N', b'E']);
-const ID_DATA: u32 = u32::from_le_bytes([b'D', b'A', b'T', b'A']);
+use crate::codec::{SyncCodec, SyncRequest, SyncResponse};
+use crate::commands::push::SENDRECV_V2;
+use crate::transport::transport::{read_frame, write_frame};
+use crate::{AdbTransportError, AdbTransports, DeviceSelector};
+use crate::Result;
 impl AdbTransports {
+    /// Downloads `path` into `output`, `cancel` stops it as it stops `shell`.
     #[async_backtrace::framed]
-    pub async fn pull<S: ToString, A: AsRef<str>>(
+    pub async fn pull<A: AsRef<str>>(
         &mut self,
-        serial: Option<S>,
+        selector: DeviceSelector,
         path: A,
-        output: &mut dyn std::io::Write,
+        output: &mut (dyn std::io::Write + Send),
+        cancel: impl Future<Output = ()>,
     ) -> Result<()> {
-        self.new_connection().await?;
-        self.may_set_serial(serial).await?;
-
-        self.transports
-            .send_command(AdbCommand::Sync, false)
-            .await?;
-        self.sync_recv(path.as_ref().to_string(), output).await?;
-        Ok(())
+        let v2 = self.has_feature(&selector, SENDRECV_V2).await?;
+        // A sync session left open on the same device is reused.
+        self.may_set_sync(selector).await?;
+        let result = tokio::select! {
+            result = self.sync_recv(path.as_ref().to_string(), output, v2) => result,
+            _ = cancel => Err(AdbTransportError::Cancelled),
+        };
+        if let Err(AdbTransportError::Cancelled) = result {
+            // The server is still sending the file, drop the session instead.
+            self.new_connection().await?;
+            return result;
+        }
+        self.consume_on_error(result)
     }
     #[async_backtrace::framed]
-    async fn sync_recv(&mut self, path: String, output: &mut dyn std::io::Write) -> Result<()> {
+    async fn sync_recv(
+        &mut self,
+        path: String,
+        output: &mut (dyn std::io::Write + Send),
+        v2: bool,
+    ) -> Result<()> {
         if path.len() > 1024 {
-            return Err(AdbTransportError::AdbError("Too long Path".into()).into());
+            return Err(AdbTransportError::InvalidPath("Too long Path".into()));
         }
         if path.contains("\n") {
-            return Err(AdbTransportError::AdbError("Path contains newline".into()).into());
+            return Err(AdbTransportError::InvalidPath("Path contains newline".into()));
         }
         if path.is_empty() {
-            return Err(AdbTransportError::AdbError("Path is empty".into()).into());
+            return Err(AdbTransportError::InvalidPath("Path is empty".into()));
         }
 
-        //TODO: Recv-2 Feature.
-        self.transports
-            .send_sync_command(crate::AdbSyncModeCommand::Recv)
-            .await?;
-        
-        let mut buf = Vec::new();
-        let path_length = (path.len() as u32).to_le_bytes(); // Convert path_length to bytes
-        buf.extend_from_slice(&path_length);
-        buf.extend_from_slice(path.as_bytes());
-        self.transports.write_all(&buf).await?;
+        let request = if v2 {
+            SyncRequest::RecvV2 { path, flags: 0 }
+        } else {
+            SyncRequest::Recv(path)
+        };
+        let mut codec = SyncCodec::default();
+        write_frame(self.transports.as_mut(), &mut codec, request).await?;
         // https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/client/file_sync_client.cpp;l=1098;drc=60c3258770b1ce3ce5bbdcff3c4a87c8f996b92f;bpv=1;bpt=1
-        let mut bytes_copied = 0;
-        let mut buffer = vec![0; SYNC_DATA_MAX as usize];
-        let mut sync_msg_data = [0_u8; 8];
         loop {
-            self.transports
-                .read_exact_(&mut sync_msg_data)
-                .await?;
-            let (id, size) = sync_msg_data.split_at(4);
-            let id = u32::from_le_bytes(id.try_into().map_err(|err| {
-                AdbTransportError::AdbError(format!("Invalid Sync Message ID Error: {}", err))
-            })?);
-            let size = u32::from_le_bytes(size.try_into().map_err(|err| {
-                AdbTransportError::AdbError(format!("Invalid Sync Message Size Error: {}", err))
-            })?);
-            // println!("id: {:?}, size: {:?}", std::str::from_utf8(&id.to_le_bytes()).unwrap_or("Invalid UTF-8"), size);
-            if id == ID_DONE ||size==0 {
-                break;
+            match read_frame(self.transports.as_mut(), &mut codec).await? {
+                SyncResponse::Data(data) => output.write_all(&data)?,
+                SyncResponse::Done => break,
+                response => return Err(response.into_error("sync_recv")),
             }
-            if id != ID_DATA {
-                return Err(AdbTransportError::AdbError(format!(
-                    "Invalid Sync Message ID: {}",
-                    std::str::from_utf8(&id.to_be_bytes()).unwrap_or("Invalid UTF-8")
-                ))
-                .into());
-            }
-            buffer.resize(size as usize, 0);
-            self.transports
-                .read_exact_(&mut buffer)
-                .await?;
-            output
-                .write_all(&buffer)?;
-            bytes_copied += size;
         }
         Ok(())
     }
 }
+
+#[tokio::test]
+async fn test_pull_cancel() {
+    use crate::transport::stream_transport::FakeServer;
+    use crate::SessionState;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    // adbd sends the first chunk of the file, then stalls.
+    let server = FakeServer::new(|_| b"OKAY".to_vec()).with_service(|_, mut stream| {
+        Box::pin(async move {
+            let mut recv = [0u8; 10];
+            stream.read_exact(&mut recv).await.unwrap();
+            stream.write_all(b"DATA\x03\0\0\0abc").await.unwrap();
+            std::future::pending::<()>().await
+        })
+    });
+    let log = server.log.clone();
+    let mut adb = AdbTransports::with_connector(server).await.unwrap();
+    let mut output = Vec::new();
+    let cancel = tokio::time::sleep(std::time::Duration::from_millis(50));
+    let result = adb
+        .pull(DeviceSelector::Any, "/a", &mut output, cancel)
+        .await;
+    assert!(matches!(result, Err(AdbTransportError::Cancelled)));
+    assert_eq!(output, b"abc");
+    assert_eq!(adb.state(), &SessionState::Idle);
+    assert_eq!(log.lock().unwrap().last().unwrap(), "connect");
+}
@@ -1,87 +1,112 @@
-use std::{io::Read, path::PathBuf};
+use std::future::Future;
+use std::io::Read;
 
-use crate::{AdbCommand, AdbRespStatus, AdbTransportError, AdbTransports};
-use anyhow::Result;
-const SYNC_DATA_MAX: usize = 64 * 1024;
-const ID_DONE: u32 = u32::from_be_bytes([b'D', b'O', b'N', b'E']);
-const ID_DATA: u32 = u32::from_be_bytes([b'D', b'A', b'T', b'A']);
+use crate::codec::{SyncCodec, SyncRequest, SyncResponse, SYNC_DATA_MAX};
+use crate::transport::transport::{read_frame, write_frame};
+use crate::{AdbTransportError, AdbTransports, DeviceSelector};
+use crate::Result;
+use bytes::Bytes;
+pub(crate) const SENDRECV_V2: &str = "sendrecv_v2";
 impl AdbTransports {
+    /// Uploads `stream` to `path`, `cancel` stops it as it stops `shell`.
     #[async_backtrace::framed]
-    pub async fn push<S: ToString, A: AsRef<str>>(
+    pub async fn push<A: AsRef<str>>(
         &mut self,
-        serial: Option<S>,
-        stream: &mut dyn Read,
+        selector: DeviceSelector,
+        stream: &mut (dyn Read + Send),
         path: A,
+        cancel: impl Future<Output = ()>,
     ) -> Result<()> {
-        self.new_connection().await?;
-        self.may_set_serial(serial).await?;
-
-
-        self.transports
-            .send_command(AdbCommand::Sync, false)
-            .await?;
-        self.sync_send(stream, path.as_ref().to_string()).await?;
-        Ok(())
+        let v2 = self.has_feature(&selector, SENDRECV_V2).await?;
+        // A sync session left open on the same device is reused.
+        self.may_set_sync(selector).await?;
+        let result = tokio::select! {
+            result = self.sync_send(stream, path.as_ref().to_string(), v2) => result,
+            _ = cancel => Err(AdbTransportError::Cancelled),
+        };
+        if let Err(AdbTransportError::Cancelled) = result {
+            // Without DONE the device discards the partial file once the session closes.
+            self.new_connection().await?;
+            return result;
+        }
+        self.consume_on_error(result)
     }
-    async fn sync_send(&mut self, input_stream: &mut dyn Read, path: String) -> Result<()> {
-        //TODO: change premision
-        let path_with_premission = format!("{},{}", path, "0644");
+    async fn sync_send(&mut self, input_stream: &mut (dyn Read + Send), path: String, v2: bool) -> Result<()> {
         if path.len() > 1024 {
-            return Err(AdbTransportError::AdbError("Too long Path".into()).into());
+            return Err(AdbTransportError::InvalidPath("Too long Path".into()));
         }
         if path.contains("\n") {
-            return Err(AdbTransportError::AdbError("Path contains newline".into()).into());
+            return Err(AdbTransportError::InvalidPath("Path contains newline".into()));
         }
         if path.is_empty() {
-            return Err(AdbTransportError::AdbError("Path is empty".into()).into());
+            return Err(AdbTransportError::InvalidPath("Path is empty".into()));
         }
         
-        //TODO: Recv-2 Feature.
-        self.transports
-            .send_sync_command(crate::AdbSyncModeCommand::Send)
-            .await?;
-        let mut buf = Vec::new();
-        let path_length = (path_with_premission.len() as u32).to_le_bytes(); // Convert path_length to bytes
-        buf.extend_from_slice(&path_length);
-        buf.extend_from_slice(path_with_premission.as_bytes());
-        self.transports.write_all(&buf).await?;
+        //TODO: change premision
+        let request = if v2 {
+            SyncRequest::SendV2 {
+                path,
+                mode: 0o644,
+                flags: 0,
+            }
+        } else {
+            SyncRequest::Send(format!("{},{}", path, "0644"))
+        };
+        let mut codec = SyncCodec::default();
+        write_frame(self.transports.as_mut(), &mut codec, request).await?;
 
-        let mut buffer = vec![0; SYNC_DATA_MAX as usize];
+        let mut buffer = vec![0; self.buffer_size.min(SYNC_DATA_MAX)];
         loop {
             let bytes_read = input_stream.read(&mut buffer)?;
             if bytes_read == 0 {
                 break;
             }
-            let mut chunk_len_buf = [0_u8; 4];
-            chunk_len_buf.copy_from_slice(&(bytes_read as u32).to_le_bytes());
-            self.transports.write_all(b"DATA").await?;
-            self.transports.write_all(&chunk_len_buf).await?;
-            self.transports.write_all(&buffer[..bytes_read]).await?;
+            let chunk = Bytes::copy_from_slice(&buffer[..bytes_read]);
+            write_frame(self.transports.as_mut(), &mut codec, SyncRequest::Data(chunk)).await?;
         }
-        self.transports.write_all(b"DONE").await?;
         let last_modified =
             match std::time::SystemTime::now().duration_since(std::time::SystemTime::UNIX_EPOCH) {
                 Ok(n) => n,
                 Err(_) => panic!("SystemTime before UNIX EPOCH!"),
             };
-        let last_modified_buf = (last_modified.as_secs() as u32).to_le_bytes();
-        self.transports.write_all(&last_modified_buf).await?;
-        let mut request_status = [0; 4];
-        self.transports.read_exact_(&mut request_status).await?;
-
-        match AdbRespStatus::from(request_status) {
-            AdbRespStatus::Okay => Ok(()),
-            AdbRespStatus::Fail(_) => {
-                let length = self
-                    .transports
-                    .get_length()
-                    .await
-                    .map_err(|err| AdbTransportError::InvalidResponse("sync_send".to_string(),Some(err.to_string())))?;
-                let mut message = vec![0u8; length];
-                self.transports.read_exact_(&mut message).await?;
-                let message = std::str::from_utf8(&message)?;
-                Err(AdbTransportError::AdbError(message.to_string()).into())
-            }
+        let done = SyncRequest::Done(last_modified.as_secs() as u32);
+        write_frame(self.transports.as_mut(), &mut codec, done).await?;
+        match read_frame(self.transports.as_mut(), &mut codec).await? {
+            SyncResponse::Okay => Ok(()),
+            response => Err(response.into_error("sync_send")),
         }
     }
 }
+
+#[tokio::test]
+async fn test_push_cancel() {
+    use crate::transport::stream_transport::FakeServer;
+    use crate::SessionState;
+
+    // adbd takes the sync request, then stops reading the upload.
+    let server = FakeServer::new(|_| b"OKAY".to_vec()).with_service(|_, stream| {
+        Box::pin(async move {
+            let _stream = stream;
+            std::future::pending::<()>().await
+        })
+    });
+    let log = server.log.clone();
+    let mut adb = AdbTransports::with_connector(server).await.unwrap();
+    let mut upload = std::io::repeat(0).take(1 << 20);
+    let cancel = tokio::time::sleep(std::time::Duration::from_millis(50));
+    let result = adb
+        .push(
+            DeviceSelector::Any,
+            &mut upload,
+            "/data/local/tmp/big",
+            cancel,
+        )
+        .await;
+    assert!(matches!(result, Err(AdbTransportError::Cancelled)));
+    // The half-sent file is abandoned with its connection.
+    assert_eq!(adb.state(), &SessionState::Idle);
+    assert_eq!(
+        *log.lock().unwrap(),
+        ["connect", "host:transport-any", "sync:", "connect"]
+    );
+}
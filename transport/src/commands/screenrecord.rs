@@ -0,0 +1,40 @@
+use std::future::Future;
+
+use crate::options::screenrecord::ScreenRecordOptions;
+use crate::{AdbCommand, AdbTransports, DeviceSelector};
+use crate::Result;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+impl AdbTransports {
+    /// Streams raw H.264 from `screenrecord` into `output` until the device stops
+    /// recording or `cancel` resolves. Returns the number of bytes written.
+    #[async_backtrace::framed]
+    pub async fn screenrecord<W: AsyncWrite + Unpin + ?Sized>(
+        &mut self,
+        selector: DeviceSelector,
+        options: ScreenRecordOptions,
+        output: &mut W,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<u64> {
+        // `exec:` keeps the stream binary-clean, `shell:` would mangle it through a pty.
+        self.open_service(selector, AdbCommand::Exec(options.to_command()))
+            .await?;
+        tokio::pin!(cancel);
+        let mut buffer = vec![0u8; self.buffer_size];
+        let mut bytes_written = 0;
+        loop {
+            let read = tokio::select! {
+                _ = &mut cancel => break,
+                read = self.transports.read_(&mut buffer) => read?,
+            };
+            if read == 0 {
+                break;
+            }
+            output.write_all(&buffer[..read]).await?;
+            bytes_written += read as u64;
+        }
+        output.flush().await?;
+        // Closing the socket makes screenrecord hit EPIPE and finalize on the device.
+        self.new_connection().await?;
+        Ok(bytes_written)
+    }
+}
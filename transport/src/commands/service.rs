@@ -0,0 +1,241 @@
+use std::time::Duration;
+
+use crate::options::reboot::RebootTarget;
+use crate::result::device::{Device, DeviceState};
+use crate::result::service::{ModeReply, RemountReply, RootReply, UnrootReply, VerityReply};
+use crate::{AdbCommand, AdbTransportError, AdbTransports, DeviceSelector};
+use crate::Result;
+use futures_util::stream::BoxStream;
+use futures_util::StreamExt;
+impl AdbTransports {
+    #[async_backtrace::framed]
+    pub async fn reboot(&mut self, selector: DeviceSelector, target: RebootTarget) -> Result<()> {
+        self.service_reply(selector, AdbCommand::Reboot(target))
+            .await?;
+        Ok(())
+    }
+    #[async_backtrace::framed]
+    pub async fn root(&mut self, selector: DeviceSelector) -> Result<RootReply> {
+        let reply = self.service_reply(selector, AdbCommand::Root).await?;
+        Ok(RootReply::from(reply.as_str()))
+    }
+    #[async_backtrace::framed]
+    pub async fn unroot(&mut self, selector: DeviceSelector) -> Result<UnrootReply> {
+        let reply = self.service_reply(selector, AdbCommand::Unroot).await?;
+        Ok(UnrootReply::from(reply.as_str()))
+    }
+    #[async_backtrace::framed]
+    pub async fn remount(&mut self, selector: DeviceSelector) -> Result<RemountReply> {
+        let reply = self.service_reply(selector, AdbCommand::Remount).await?;
+        Ok(RemountReply::from(reply.as_str()))
+    }
+    #[async_backtrace::framed]
+    pub async fn tcpip(&mut self, selector: DeviceSelector, port: u16) -> Result<ModeReply> {
+        let reply = self
+            .service_reply(selector, AdbCommand::Tcpip(port))
+            .await?;
+        Ok(ModeReply::from(reply.as_str()))
+    }
+    #[async_backtrace::framed]
+    pub async fn usb(&mut self, selector: DeviceSelector) -> Result<ModeReply> {
+        let reply = self.service_reply(selector, AdbCommand::Usb).await?;
+        Ok(ModeReply::from(reply.as_str()))
+    }
+    #[async_backtrace::framed]
+    pub async fn enable_verity(&mut self, selector: DeviceSelector) -> Result<VerityReply> {
+        let reply = self
+            .service_reply(selector, AdbCommand::EnableVerity)
+            .await?;
+        Ok(VerityReply::from(reply.as_str()))
+    }
+    #[async_backtrace::framed]
+    pub async fn disable_verity(&mut self, selector: DeviceSelector) -> Result<VerityReply> {
+        let reply = self
+            .service_reply(selector, AdbCommand::DisableVerity)
+            .await?;
+        Ok(VerityReply::from(reply.as_str()))
+    }
+    /// Starts watching `serial` before a service that restarts adbd is sent, so a device
+    /// that is back before `root` or `reboot` returns is still seen leaving and coming back.
+    ///
+    /// ```no_run
+    /// # async fn run(mut adb: transport::AdbTransports) -> transport::Result<()> {
+    /// use std::time::Duration;
+    /// use transport::result::device::DeviceState;
+    /// use transport::DeviceSelector;
+    /// let selector = DeviceSelector::Serial("emulator-5554".to_string());
+    /// let watch = adb.watch_reconnect("emulator-5554", DeviceState::Device).await?;
+    /// adb.root(selector).await?;
+    /// watch.wait(Duration::from_secs(30)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[async_backtrace::framed]
+    pub async fn watch_reconnect<S: ToString>(
+        &self,
+        serial: S,
+        state: DeviceState,
+    ) -> Result<ReconnectWatch> {
+        Ok(ReconnectWatch {
+            serial: serial.to_string(),
+            state,
+            snapshots: self
+                .track_snapshots(AdbCommand::TrackDevices)
+                .await?
+                .boxed(),
+        })
+    }
+    /// Waits until `serial` drops off the server and comes back in `state`. It only starts
+    /// watching now, when adbd may already be back use `watch_reconnect` instead.
+    #[async_backtrace::framed]
+    pub async fn wait_for_reconnect<S: ToString>(
+        &self,
+        serial: S,
+        state: DeviceState,
+        timeout: Duration,
+    ) -> Result<Device> {
+        self.watch_reconnect(serial, state)
+            .await?
+            .wait(timeout)
+            .await
+    }
+    /// Runs a daemon service and returns its textual reply. The daemon closes the stream
+    /// once it has answered, so the session is reset afterwards.
+    async fn service_reply(
+        &mut self,
+        selector: DeviceSelector,
+        command: AdbCommand,
+    ) -> Result<String> {
+        let reboot = matches!(command, AdbCommand::Reboot(_));
+        self.open_service(selector, command).await?;
+        let reply = self.transports.read_to_end().await;
+        let reconnected = self.new_connection().await;
+        let reply = match reply {
+            // Once adbd took the request the device is going down, however the stream ends.
+            Err(_) if reboot => Vec::new(),
+            reply => reply?,
+        };
+        reconnected?;
+        Ok(String::from_utf8_lossy(&reply).to_string())
+    }
+}
+/// Device changes recorded since `AdbTransports::watch_reconnect`.
+pub struct ReconnectWatch {
+    serial: String,
+    state: DeviceState,
+    snapshots: BoxStream<'static, Result<Vec<Device>>>,
+}
+impl ReconnectWatch {
+    /// Waits until the device has dropped off the server and come back in the watched
+    /// state. Fails once `timeout` has elapsed.
+    #[async_backtrace::framed]
+    pub async fn wait(self, timeout: Duration) -> Result<Device> {
+        let serial = self.serial;
+        tokio::time::timeout(timeout, until_back(self.snapshots, &serial, self.state))
+            .await
+            .map_err(|_| {
+                AdbTransportError::Timeout(format!("waiting for {} to reconnect", serial))
+            })?
+    }
+}
+async fn until_back(
+    mut snapshots: BoxStream<'static, Result<Vec<Device>>>,
+    serial: &str,
+    state: DeviceState,
+) -> Result<Device> {
+    let mut disconnected = false;
+    while let Some(devices) = snapshots.next().await {
+        let device = devices?.into_iter().find(|device| device.serial == serial);
+        match device {
+            Some(device) if disconnected && device.state == state => return Ok(device),
+            Some(device) if device.state == state => {}
+            _ => disconnected = true,
+        }
+    }
+    Err(AdbTransportError::EOF)
+}
+
+#[tokio::test]
+async fn test_watch_reconnect() {
+    use crate::transport::stream_transport::FakeServer;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tokio::io::AsyncWriteExt;
+
+    // adbd is gone and back before `root` has even returned. A tracker opened before
+    // the restart sees it leave and return, one opened after only sees it there.
+    static RESTARTED: AtomicBool = AtomicBool::new(false);
+    let server = FakeServer::new(|request| match request {
+        "host:track-devices" if RESTARTED.load(Ordering::SeqCst) => b"OKAY0009A\tdevice\n".to_vec(),
+        "host:track-devices" => b"OKAY0009A\tdevice\n00000009A\tdevice\n".to_vec(),
+        _ => b"OKAY".to_vec(),
+    })
+    .with_service(|_, mut stream| {
+        Box::pin(async move {
+            RESTARTED.store(true, Ordering::SeqCst);
+            stream
+                .write_all(b"restarting adbd as root\n")
+                .await
+                .unwrap();
+        })
+    });
+    let mut adb = AdbTransports::with_connector(server).await.unwrap();
+    let watch = adb.watch_reconnect("A", DeviceState::Device).await.unwrap();
+    assert_eq!(
+        adb.root(DeviceSelector::Any).await.unwrap(),
+        RootReply::Restarting
+    );
+    let device = watch.wait(Duration::from_secs(5)).await.unwrap();
+    assert_eq!(device.serial, "A");
+
+    let late = adb
+        .wait_for_reconnect("A", DeviceState::Device, Duration::from_millis(50))
+        .await;
+    assert!(matches!(late, Err(AdbTransportError::Timeout(_))));
+}
+#[tokio::test]
+async fn test_reboot_stream_lost() {
+    use crate::options::timeout::Timeouts;
+    use crate::transport::stream_transport::FakeServer;
+
+    // The device goes down without closing the stream, so the read fails.
+    let server = FakeServer::new(|request| match request {
+        "host:devices" => b"OKAY0000".to_vec(),
+        _ => b"OKAY".to_vec(),
+    })
+    .with_service(|_, stream| {
+        Box::pin(async move {
+            let _stream = stream;
+            std::future::pending().await
+        })
+    });
+    let log = server.log.clone();
+    let mut adb = AdbTransports::builder()
+        .connector(server)
+        .timeouts(Timeouts {
+            idle_read: Some(Duration::from_millis(50)),
+            ..Timeouts::default()
+        })
+        .build()
+        .await
+        .unwrap();
+    adb.reboot(DeviceSelector::Any, RebootTarget::default())
+        .await
+        .unwrap();
+    // Other services still fail, and the session is reset either way.
+    assert!(matches!(
+        adb.root(DeviceSelector::Any).await,
+        Err(AdbTransportError::Timeout(_))
+    ));
+    let log = log.lock().unwrap();
+    assert_eq!(
+        log[1..],
+        [
+            "host:transport-any",
+            "reboot:",
+            "connect",
+            "host:transport-any",
+            "root:",
+            "connect"
+        ]
+    );
+}
@@ -1,30 +1,37 @@
-use crate::utils::check_path;
-use crate::{AdbCommand, AdbTransportError, AdbTransports};
-use anyhow::{Ok, Result};
-use bytes::{buf, Bytes};
+use std::future::Future;
+
+use crate::Result;
+use crate::{AdbCommand, AdbTransportError, AdbTransports, DeviceSelector};
 impl AdbTransports {
+    /// Runs `cmd`, handing its output to `callback` as it arrives. Fails with `Cancelled`
+    /// and closes the session when `cancel`, e.g. `token.cancelled()`, resolves first.
     #[async_backtrace::framed]
-    pub async fn shell<S: ToString>(
+    pub async fn shell(
         &mut self,
-        serial: Option<S>,
+        selector: DeviceSelector,
         cmd: Vec<String>,
         callback: impl Fn(Vec<u8>),
+        cancel: impl Future<Output = ()>,
     ) -> Result<()> {
-        self.may_set_serial(serial).await?;
-
         let cmd = cmd
-        //     .into_iter()
-        //     .map(|str| str.replace(" ", "\\ "))
-        //     .collect::<Vec<String>>()
+            //     .into_iter()
+            //     .map(|str| str.replace(" ", "\\ "))
+            //     .collect::<Vec<String>>()
             .join(" ");
         // println!("cmd {:?}", cmd.clone());
 
-        self.transports
-            .send_command(AdbCommand::ShellExec(cmd), false)
+        self.open_service(selector, AdbCommand::ShellExec(cmd))
             .await?;
-        let mut buffer = [0u8;100];
-        loop{
-            let read = self.transports.read_(&mut buffer).await?;
+        tokio::pin!(cancel);
+        let mut buffer = vec![0u8; self.buffer_size];
+        loop {
+            let read = tokio::select! {
+                _ = &mut cancel => {
+                    self.new_connection().await?;
+                    return Err(AdbTransportError::Cancelled);
+                }
+                read = self.transports.read_(&mut buffer) => read?,
+            };
             if read == 0 {
                 break;
             }
@@ -54,24 +61,78 @@ impl AdbTransports {
 
         Ok(())
     }
+    /// Runs `cmd` and collects its whole output, leaving the session ready for the next call.
+    #[async_backtrace::framed]
+    pub async fn shell_output<C: ToString>(
+        &mut self,
+        selector: DeviceSelector,
+        cmd: C,
+    ) -> Result<Vec<u8>> {
+        self.open_service(selector, AdbCommand::ShellExec(cmd.to_string()))
+            .await?;
+        let output = self.transports.read_to_end().await?;
+        self.new_connection().await?;
+        Ok(output)
+    }
     #[async_backtrace::framed]
-    pub async fn mkdir<S: ToString, A: AsRef<str>>(
+    pub async fn mkdir<A: AsRef<str>>(
         &mut self,
-        serial: Option<S>,
+        selector: DeviceSelector,
         path: A,
     ) -> Result<Vec<u8>> {
         let path = path.as_ref();
-        let res_ = check_path(path.to_string())?;
-        if !res_{
-            return Err(anyhow::anyhow!("Path is not allowed"));
-        }
+        self.path_policy.check(path)?;
 
-        self.may_set_serial(serial).await?;
-
-        let cmd = format!("mkdir {}", path);
-        self.transports
-            .send_command(AdbCommand::ShellExec(cmd), false)
-            .await
-        
+        let cmd = format!("mkdir {}", shell_quote(path));
+        self.open_service(selector, AdbCommand::ShellExec(cmd))
+            .await?;
+        // Wait for mkdir to finish, closing early could kill it.
+        self.transports.read_to_end().await
     }
 }
+/// Quotes `arg` for the device shell, so a name with spaces, quotes or `;` stays one word.
+pub(crate) fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+#[test]
+fn test_shell_quote() {
+    assert_eq!(
+        shell_quote("/data/local/tmp/a b.apk"),
+        "'/data/local/tmp/a b.apk'"
+    );
+    assert_eq!(
+        shell_quote("a'; rm -rf /sdcard; '.apk"),
+        r"'a'\''; rm -rf /sdcard; '\''.apk'"
+    );
+}
+#[tokio::test]
+async fn test_shell_cancel() {
+    use crate::transport::stream_transport::FakeServer;
+    use crate::SessionState;
+    use tokio::io::AsyncWriteExt;
+
+    // A command that prints a line and then runs forever.
+    let server = FakeServer::new(|_| b"OKAY".to_vec()).with_service(|_, mut stream| {
+        Box::pin(async move {
+            stream.write_all(b"started\n").await.unwrap();
+            std::future::pending::<()>().await
+        })
+    });
+    let log = server.log.clone();
+    let mut adb = AdbTransports::with_connector(server).await.unwrap();
+    let output = std::sync::Mutex::new(Vec::new());
+    let cancel = tokio::time::sleep(std::time::Duration::from_millis(50));
+    let result = adb
+        .shell(
+            DeviceSelector::Any,
+            vec!["top".to_string()],
+            |chunk| output.lock().unwrap().extend(chunk),
+            cancel,
+        )
+        .await;
+    assert!(matches!(result, Err(AdbTransportError::Cancelled)));
+    assert_eq!(output.into_inner().unwrap(), b"started\n");
+    assert_eq!(adb.state(), &SessionState::Idle);
+    assert_eq!(log.lock().unwrap().last().unwrap(), "connect");
+}
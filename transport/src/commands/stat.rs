@@ -1,32 +1,27 @@
-use crate::result::device::Device;
+use crate::codec::{SyncCodec, SyncRequest, SyncResponse};
 use crate::result::stat::StatInfo;
-use crate::{result::device::Devices, AdbCommand, AdbTransportError, AdbTransports};
-use anyhow::Result;
+use crate::transport::transport::{read_frame, write_frame};
+use crate::{AdbTransports, DeviceSelector};
+use crate::Result;
 
 impl AdbTransports {
     #[async_backtrace::framed]
-    pub async fn sync_stat<S: ToString>(
+    pub async fn sync_stat(
         &mut self,
         path: String,
-        serial: Option<S>,
+        selector: DeviceSelector,
     ) -> Result<StatInfo> {
-        // self.new_connection().await?;
-        self.may_set_serial(serial).await?;
-        self.may_set_sync().await?;
-
-        self.sync_stat_(path).await
+        self.may_set_sync(selector).await?;
+        let result = self.sync_stat_(path).await;
+        self.consume_on_error(result)
     }
     #[async_backtrace::framed]
     async fn sync_stat_(&mut self, path: String) -> Result<StatInfo> {
-        self.transports
-            .send_sync_command(crate::AdbSyncModeCommand::Stat)
-            .await?;
-        let mut buf = Vec::new();
-        let path_length = (path.len() as u32).to_le_bytes(); // Convert path_length to bytes
-        buf.extend_from_slice(&path_length);
-        buf.extend_from_slice(path.as_bytes());
-        self.transports.write_all(&buf).await?;
-        let msg = StatInfo::from_le_bytes_transport(self.transports.as_mut()).await?;
-        Ok(msg)
+        let mut codec = SyncCodec::default();
+        write_frame(self.transports.as_mut(), &mut codec, SyncRequest::Stat(path)).await?;
+        match read_frame(self.transports.as_mut(), &mut codec).await? {
+            SyncResponse::Stat(stat) => Ok(stat),
+            response => Err(response.into_error("sync_stat")),
+        }
     }
 }
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+
+use crate::commands::devices::read_snapshot;
+use crate::options::retry::RetryPolicy;
+use crate::result::device::Device;
+use crate::result::track::{diff_devices, diff_devices_across_restart, TrackerEvent};
+use crate::transport::transport::AdbTransport;
+use crate::Result;
+use crate::{AdbCommand, AdbTransportError, AdbTransports};
+use futures_util::{stream, Stream};
+impl AdbTransports {
+    /// Like `track_devices`, but survives adb server restarts: the tracker reconnects
+    /// following `policy`, and the first list after reconnecting is diffed against the
+    /// last one seen so no change is lost. Ends with an error once `policy` gives up.
+    #[async_backtrace::framed]
+    pub async fn track_devices_resilient(
+        &self,
+        long: bool,
+        policy: RetryPolicy,
+    ) -> Result<impl Stream<Item = Result<TrackerEvent>>> {
+        let mut transport = self.transports.duplicate().await?;
+        transport.send_command(track_command(long), false).await?;
+        let tracker = Tracker {
+            transport,
+            long,
+            policy,
+            known: Vec::new(),
+            pending: VecDeque::new(),
+            connected: true,
+            restarted: false,
+        };
+        Ok(stream::unfold(Some(tracker), |tracker| async move {
+            let mut tracker = tracker?;
+            match tracker.next_event().await {
+                Ok(event) => Some((Ok(event), Some(tracker))),
+                Err(err) => Some((Err(err), None)),
+            }
+        }))
+    }
+}
+fn track_command(long: bool) -> AdbCommand {
+    if long {
+        AdbCommand::TrackDevicesLong
+    } else {
+        AdbCommand::TrackDevices
+    }
+}
+struct Tracker {
+    transport: Box<dyn AdbTransport>,
+    long: bool,
+    policy: RetryPolicy,
+    known: Vec<Device>,
+    pending: VecDeque<TrackerEvent>,
+    connected: bool,
+    /// The next list comes from a restarted server, with new transport ids.
+    restarted: bool,
+}
+impl Tracker {
+    async fn next_event(&mut self) -> Result<TrackerEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            if !self.connected {
+                self.reconnect().await?;
+                continue;
+            }
+            match read_snapshot(self.transport.as_mut()).await {
+                Ok(devices) => {
+                    let events = if self.restarted {
+                        diff_devices_across_restart(&self.known, &devices)
+                    } else {
+                        diff_devices(&self.known, &devices)
+                    };
+                    self.pending
+                        .extend(events.into_iter().map(TrackerEvent::Device));
+                    self.known = devices;
+                    self.restarted = false;
+                }
+                // Nothing changed for longer than `idle_read` and the transport closed the
+                // connection. The server is still there, subscribe again without a word.
+                Err(AdbTransportError::Timeout(_)) if self.resubscribe().await.is_ok() => {}
+                // Any other failed read means the server went away, the devices it knew
+                // of are kept until the next list tells otherwise.
+                Err(_) => {
+                    self.connected = false;
+                    self.pending.push_back(TrackerEvent::ServerDisconnected);
+                }
+            }
+        }
+    }
+    async fn reconnect(&mut self) -> Result<()> {
+        let mut attempt = 0;
+        loop {
+            tokio::time::sleep(self.policy.delay(attempt)).await;
+            match self.resubscribe().await {
+                Ok(()) => {
+                    self.connected = true;
+                    self.restarted = true;
+                    self.pending.push_back(TrackerEvent::ServerReconnected);
+                    return Ok(());
+                }
+                Err(err) => {
+                    attempt += 1;
+                    if !self.policy.should_retry(attempt) {
+                        return Err(err);
+                    }
+                }
+            }
+        }
+    }
+    async fn resubscribe(&mut self) -> Result<()> {
+        self.transport.reconnect().await?;
+        self.transport
+            .send_command(track_command(self.long), false)
+            .await?;
+        Ok(())
+    }
+}
+
+/// A tracker connection that is sent `devices`, and that the server then keeps open
+/// or hangs up.
+#[cfg(test)]
+fn tracked(devices: &str, hang_up: bool) -> crate::transport::stream_transport::FakeConnect {
+    let reply = format!("OKAY{:04x}{}", devices.len(), devices).into_bytes();
+    crate::transport::stream_transport::FakeConnect::Answer { reply, hang_up }
+}
+#[cfg(test)]
+async fn tracker_events(
+    script: Vec<crate::transport::stream_transport::FakeConnect>,
+    timeouts: crate::options::timeout::Timeouts,
+    count: usize,
+) -> Vec<TrackerEvent> {
+    use futures_util::StreamExt;
+
+    use crate::transport::stream_transport::FakeServer;
+
+    let server = FakeServer::new(|_| Vec::new()).with_script(script);
+    let adb = AdbTransports::builder()
+        .connector(server)
+        .timeouts(timeouts)
+        .build()
+        .await
+        .unwrap();
+    let policy = RetryPolicy {
+        initial_delay: std::time::Duration::from_millis(1),
+        max_attempts: Some(5),
+        ..RetryPolicy::default()
+    };
+    let events = adb.track_devices_resilient(true, policy).await.unwrap();
+    events
+        .take(count)
+        .map(Result::unwrap)
+        .collect::<Vec<_>>()
+        .await
+}
+#[tokio::test]
+async fn test_tracker_server_restart() {
+    use crate::result::track::DeviceEvent;
+    use crate::transport::stream_transport::FakeConnect;
+
+    let before = "A\tdevice usb:1-1 transport_id:1\nB\tdevice usb:1-2 transport_id:2\n";
+    let after = "A\tdevice usb:1-1 transport_id:5\nC\tdevice usb:1-3 transport_id:6\n";
+    let events = tracker_events(
+        // The session, the tracker, a refused connect while the server restarts, then
+        // the restarted server with new transport ids.
+        vec![
+            tracked("", false),
+            tracked(before, true),
+            FakeConnect::Refuse,
+            tracked(after, false),
+        ],
+        Default::default(),
+        6,
+    )
+    .await;
+    let (before, after) = (Device::parse_list(before), Device::parse_list(after));
+    assert_eq!(
+        events,
+        [
+            TrackerEvent::Device(DeviceEvent::Added(before[0].clone())),
+            TrackerEvent::Device(DeviceEvent::Added(before[1].clone())),
+            TrackerEvent::ServerDisconnected,
+            TrackerEvent::ServerReconnected,
+            // A stayed connected, its new transport id is no change.
+            TrackerEvent::Device(DeviceEvent::Removed(before[1].clone())),
+            TrackerEvent::Device(DeviceEvent::Added(after[1].clone())),
+        ]
+    );
+}
+#[tokio::test]
+async fn test_tracker_idle_timeout() {
+    use crate::options::timeout::Timeouts;
+    use crate::result::track::DeviceEvent;
+
+    let before = "A\tdevice usb:1-1 transport_id:1\n";
+    let after = "A\tdevice usb:1-1 transport_id:1\nB\tdevice usb:1-2 transport_id:2\n";
+    let events = tracker_events(
+        vec![
+            tracked("", false),
+            tracked(before, false),
+            tracked(after, false),
+        ],
+        Timeouts {
+            idle_read: Some(std::time::Duration::from_millis(50)),
+            ..Timeouts::default()
+        },
+        2,
+    )
+    .await;
+    // The quiet connection is replaced without reporting a server restart.
+    let after = Device::parse_list(after);
+    assert_eq!(
+        events,
+        [
+            TrackerEvent::Device(DeviceEvent::Added(after[0].clone())),
+            TrackerEvent::Device(DeviceEvent::Added(after[1].clone())),
+        ]
+    );
+}
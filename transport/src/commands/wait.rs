@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use crate::options::wait::{WaitForOptions, WaitState};
+use crate::codec::HostCodec;
+use crate::transport::transport::read_frame;
+use crate::{AdbCommand, AdbTransportError, AdbTransports, DeviceSelector};
+use crate::Result;
+const BOOT_COMPLETED_POLL_INTERVAL: Duration = Duration::from_secs(1);
+impl AdbTransports {
+    /// Blocks until the device reaches `options.state` on `options.transport`, like
+    /// `adb wait-for-<transport>-<state>`.
+    #[async_backtrace::framed]
+    pub async fn wait_for(
+        &mut self,
+        selector: DeviceSelector,
+        options: WaitForOptions,
+    ) -> Result<()> {
+        let result = match options.timeout {
+            Some(timeout) => {
+                match tokio::time::timeout(timeout, self.wait_for_(selector, &options)).await {
+                    Ok(result) => result,
+                    Err(_) => Err(AdbTransportError::Timeout(format!(
+                        "waiting for {}-{}",
+                        options.transport, options.state
+                    ))),
+                }
+            }
+            None => self.wait_for_(selector, &options).await,
+        };
+        // A timed out request is still parked on the server, drop it. Failing to
+        // reconnect must not hide why the wait failed.
+        let reconnected = self.new_connection().await;
+        result.and(reconnected)
+    }
+    async fn wait_for_(
+        &mut self,
+        selector: DeviceSelector,
+        options: &WaitForOptions,
+    ) -> Result<()> {
+        // The first OKAY acknowledges the request, the second one arrives once the state is reached.
+        self.open_host_service(AdbCommand::WaitFor(
+            selector.clone(),
+            options.transport,
+            options.state,
+        ))
+        .await?;
+        read_frame(self.transports.as_mut(), &mut HostCodec)
+            .await?
+            .into_result()?;
+        self.new_connection().await?;
+        if options.boot_completed && options.state == WaitState::Device {
+            loop {
+                let output = self
+                    .shell_output(selector.clone(), "getprop sys.boot_completed")
+                    .await?;
+                if String::from_utf8_lossy(&output).trim() == "1" {
+                    break;
+                }
+                tokio::time::sleep(BOOT_COMPLETED_POLL_INTERVAL).await;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_wait_for_handshake() {
+    use crate::transport::stream_transport::FakeServer;
+
+    let server = FakeServer::new(|request| match request {
+        "host:wait-for-any-device" => b"OKAYOKAY".to_vec(),
+        "host-serial:B:wait-for-usb-recovery" => b"OKAYFAIL0014device 'B' not found".to_vec(),
+        _ => b"FAIL0014unknown host service".to_vec(),
+    });
+    let mut adb = AdbTransports::with_connector(server).await.unwrap();
+    adb.wait_for(DeviceSelector::Any, WaitForOptions::default())
+        .await
+        .unwrap();
+    let options = WaitForOptions {
+        transport: crate::options::wait::WaitTransport::Usb,
+        state: WaitState::Recovery,
+        ..WaitForOptions::default()
+    };
+    assert!(matches!(
+        adb.wait_for(DeviceSelector::Serial("B".to_string()), options).await,
+        Err(AdbTransportError::DeviceNotFound(serial)) if serial == "B"
+    ));
+    // The session is usable again after either outcome.
+    adb.wait_for(DeviceSelector::Any, WaitForOptions::default())
+        .await
+        .unwrap();
+}
+#[tokio::test]
+async fn test_wait_for_timeout() {
+    use crate::transport::stream_transport::FakeServer;
+
+    // The device never shows up, only the request is acknowledged.
+    let server = FakeServer::new(|request| match request {
+        "host:wait-for-any-device" => b"OKAY".to_vec(),
+        "host:devices" => b"OKAY0000".to_vec(),
+        _ => b"FAIL0014unknown host service".to_vec(),
+    });
+    let log = server.log.clone();
+    let mut adb = AdbTransports::with_connector(server).await.unwrap();
+    let options = WaitForOptions {
+        timeout: Some(Duration::from_millis(50)),
+        ..WaitForOptions::default()
+    };
+    assert!(matches!(
+        adb.wait_for(DeviceSelector::Any, options).await,
+        Err(AdbTransportError::Timeout(what)) if what == "waiting for any-device"
+    ));
+    // The parked request is left on its own connection.
+    assert!(adb.devices().await.unwrap().is_empty());
+    assert_eq!(
+        log.lock().unwrap()[..3],
+        ["connect", "host:wait-for-any-device", "connect"]
+    );
+}
+#[tokio::test]
+async fn test_wait_for_boot_completed() {
+    use crate::transport::stream_transport::FakeServer;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::AsyncWriteExt;
+
+    // The device is online but still booting on the first poll.
+    static POLLS: AtomicUsize = AtomicUsize::new(0);
+    let server = FakeServer::new(|request| match request {
+        "host:wait-for-any-device" => b"OKAYOKAY".to_vec(),
+        _ => b"OKAY".to_vec(),
+    })
+    .with_service(|_, mut stream| {
+        Box::pin(async move {
+            let reply: &[u8] = match POLLS.fetch_add(1, Ordering::SeqCst) {
+                0 => b"\n",
+                _ => b"1\n",
+            };
+            stream.write_all(reply).await.unwrap();
+        })
+    });
+    let log = server.log.clone();
+    let mut adb = AdbTransports::with_connector(server).await.unwrap();
+    let options = WaitForOptions {
+        boot_completed: true,
+        timeout: Some(Duration::from_secs(5)),
+        ..WaitForOptions::default()
+    };
+    adb.wait_for(DeviceSelector::Any, options).await.unwrap();
+    let log = log.lock().unwrap();
+    let polls = log
+        .iter()
+        .filter(|request| *request == "shell,raw:getprop sys.boot_completed")
+        .count();
+    assert_eq!(polls, 2);
+}
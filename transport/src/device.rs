@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use crate::options::features::FeatureDetection;
+use crate::result::dent::SyncDent;
+use crate::result::properties::parse_getprop;
+use crate::result::stat::StatInfo;
+use crate::{AdbTransports, DeviceSelector, Result};
+
+/// Runs commands on one device without repeating its selector. The handle has its own
+/// connection to the server, and keeps the device's features and properties once queried.
+///
+/// ```no_run
+/// # async fn run() -> transport::Result<()> {
+/// use transport::{AdbTransports, DeviceSelector};
+/// let adb = AdbTransports::builder().build().await?;
+/// let mut device = adb.device(DeviceSelector::Serial("emulator-5554".to_string())).await?;
+/// let sdk = device.property("ro.build.version.sdk").await?;
+/// device.install("app-debug.apk").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct DeviceHandle {
+    adb: AdbTransports,
+    selector: DeviceSelector,
+    query_features: bool,
+    features: Option<Vec<String>>,
+    properties: Option<HashMap<String, String>>,
+}
+impl AdbTransports {
+    /// Opens a handle on `selector`, on a new connection configured like this one.
+    #[async_backtrace::framed]
+    pub async fn device(&self, selector: DeviceSelector) -> Result<DeviceHandle> {
+        Ok(DeviceHandle::new(self.fork().await?, selector))
+    }
+}
+impl DeviceHandle {
+    pub(crate) fn new(adb: AdbTransports, selector: DeviceSelector) -> Self {
+        Self {
+            query_features: adb.features == FeatureDetection::Query,
+            adb,
+            selector,
+            features: None,
+            properties: None,
+        }
+    }
+    pub fn selector(&self) -> &DeviceSelector {
+        &self.selector
+    }
+    /// The features the device and server share, queried on the first call.
+    #[async_backtrace::framed]
+    pub async fn features(&mut self) -> Result<&[String]> {
+        if self.features.is_none() {
+            let features = self.adb.features(self.selector.clone()).await?;
+            if self.query_features {
+                // Transfers pick their protocol from these instead of asking again.
+                self.adb.features = FeatureDetection::Assume(features.clone());
+            }
+            self.features = Some(features);
+        }
+        Ok(self.features.as_deref().unwrap_or_default())
+    }
+    /// The output of `getprop`, read on the first call.
+    #[async_backtrace::framed]
+    pub async fn properties(&mut self) -> Result<&HashMap<String, String>> {
+        if self.properties.is_none() {
+            let output = self
+                .adb
+                .shell_output(self.selector.clone(), "getprop")
+                .await?;
+            self.properties = Some(parse_getprop(&String::from_utf8_lossy(&output)));
+        }
+        Ok(self.properties.get_or_insert_with(HashMap::new))
+    }
+    #[async_backtrace::framed]
+    pub async fn property(&mut self, name: &str) -> Result<Option<String>> {
+        Ok(self.properties().await?.get(name).cloned())
+    }
+    /// Forgets the cached features and properties, e.g. after a reboot or an OTA.
+    pub fn refresh(&mut self) {
+        self.features = None;
+        self.properties = None;
+        if self.query_features {
+            self.adb.features = FeatureDetection::Query;
+        }
+    }
+    /// See `AdbTransports::shell`.
+    #[async_backtrace::framed]
+    pub async fn shell(
+        &mut self,
+        cmd: Vec<String>,
+        callback: impl Fn(Vec<u8>),
+        cancel: impl Future<Output = ()>,
+    ) -> Result<()> {
+        self.adb
+            .shell(self.selector.clone(), cmd, callback, cancel)
+            .await
+    }
+    #[async_backtrace::framed]
+    pub async fn shell_output<C: ToString>(&mut self, cmd: C) -> Result<Vec<u8>> {
+        self.adb.shell_output(self.selector.clone(), cmd).await
+    }
+    /// Streams `logcat` with `args`, e.g. `-d` to dump the log and exit, to `callback`.
+    #[async_backtrace::framed]
+    pub async fn logcat(
+        &mut self,
+        args: Vec<String>,
+        callback: impl Fn(Vec<u8>),
+        cancel: impl Future<Output = ()>,
+    ) -> Result<()> {
+        let cmd = std::iter::once("logcat".to_string()).chain(args).collect();
+        self.shell(cmd, callback, cancel).await
+    }
+    #[async_backtrace::framed]
+    pub async fn push<A: AsRef<str>>(
+        &mut self,
+        stream: &mut (dyn Read + Send),
+        path: A,
+        cancel: impl Future<Output = ()>,
+    ) -> Result<()> {
+        self.detect_features().await?;
+        self.adb
+            .push(self.selector.clone(), stream, path, cancel)
+            .await
+    }
+    #[async_backtrace::framed]
+    pub async fn pull<A: AsRef<str>>(
+        &mut self,
+        path: A,
+        output: &mut (dyn Write + Send),
+        cancel: impl Future<Output = ()>,
+    ) -> Result<()> {
+        self.detect_features().await?;
+        self.adb
+            .pull(self.selector.clone(), path, output, cancel)
+            .await
+    }
+    #[async_backtrace::framed]
+    pub async fn list<A: AsRef<str>>(&mut self, path: A) -> Result<Vec<SyncDent>> {
+        self.adb
+            .list(path.as_ref().to_string(), self.selector.clone())
+            .await
+    }
+    #[async_backtrace::framed]
+    pub async fn stat<A: AsRef<str>>(&mut self, path: A) -> Result<StatInfo> {
+        self.adb
+            .sync_stat(path.as_ref().to_string(), self.selector.clone())
+            .await
+    }
+    /// See `AdbTransports::install`.
+    #[async_backtrace::framed]
+    pub async fn install<P: AsRef<Path>>(&mut self, apk: P) -> Result<()> {
+        self.detect_features().await?;
+        self.adb.install(self.selector.clone(), apk).await
+    }
+    async fn detect_features(&mut self) -> Result<()> {
+        if self.query_features {
+            self.features().await?;
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_device_caches_features() {
+    use crate::transport::stream_transport::FakeServer;
+
+    let server = FakeServer::new(|request| match request {
+        "host-serial:A:features" => b"OKAY0018shell_v2,cmd,sendrecv_v2".to_vec(),
+        _ => b"FAIL0014unknown host service".to_vec(),
+    });
+    let log = server.log.clone();
+    let adb = AdbTransports::builder()
+        .connector(server)
+        .feature_detection(FeatureDetection::Query)
+        .build()
+        .await
+        .unwrap();
+    let mut device = adb
+        .device(DeviceSelector::Serial("A".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        device.features().await.unwrap(),
+        ["shell_v2", "cmd", "sendrecv_v2"]
+    );
+    device.features().await.unwrap();
+    assert!(device
+        .adb
+        .has_feature(device.selector(), "cmd")
+        .await
+        .unwrap());
+    let queries = |log: &[String]| {
+        log.iter()
+            .filter(|request| *request == "host-serial:A:features")
+            .count()
+    };
+    assert_eq!(queries(&log.lock().unwrap()), 1);
+
+    device.refresh();
+    device.features().await.unwrap();
+    assert_eq!(queries(&log.lock().unwrap()), 2);
+}
+#[tokio::test]
+async fn test_device_logcat() {
+    use crate::transport::stream_transport::FakeServer;
+    use tokio::io::AsyncWriteExt;
+
+    let server = FakeServer::new(|_| b"OKAY".to_vec()).with_service(|_, mut stream| {
+        Box::pin(async move {
+            stream
+                .write_all(b"--------- beginning of main\nI/init: started\n")
+                .await
+                .unwrap();
+        })
+    });
+    let log = server.log.clone();
+    let adb = AdbTransports::with_connector(server).await.unwrap();
+    let mut device = adb
+        .device(DeviceSelector::Serial("A".to_string()))
+        .await
+        .unwrap();
+    let output = std::sync::Mutex::new(Vec::new());
+    device
+        .logcat(
+            vec!["-d".to_string()],
+            |chunk| output.lock().unwrap().extend(chunk),
+            std::future::pending(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        output.into_inner().unwrap(),
+        b"--------- beginning of main\nI/init: started\n"
+    );
+    assert_eq!(
+        log.lock().unwrap()[2..],
+        ["host:transport:A", "shell,raw:logcat -d"]
+    );
+}
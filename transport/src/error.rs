@@ -0,0 +1,167 @@
+use std::array::TryFromSliceError;
+use std::num::ParseIntError;
+use std::str::Utf8Error;
+use std::string::FromUtf8Error;
+
+pub type Result<T, E = AdbTransportError> = std::result::Result<T, E>;
+
+#[derive(thiserror::Error, Debug)]
+pub enum AdbTransportError {
+    #[error("IO Error {0:?}")]
+    IoError(#[from] std::io::Error),
+    /// A FAIL reply that matches none of the known messages.
+    #[error("Adb Transport Error: {0:?}")]
+    AdbError(String),
+    #[error("Invalid Response @{0} :{1:?}")]
+    InvalidResponse(String, Option<String>),
+    #[error("Response Conversion Error: {0:?}")]
+    ConversionError(String),
+    #[error("EOF")]
+    EOF,
+    /// The operation named in the message ran out of time, its connection was closed.
+    #[error("{0} timed out")]
+    Timeout(String),
+    /// The caller cancelled the operation, its connection was closed.
+    #[error("cancelled")]
+    Cancelled,
+    /// The adb server could not be reached, it is likely not running.
+    #[error("adb server unreachable: {0}")]
+    ServerUnreachable(std::io::Error),
+    #[error("invalid server address: {0}")]
+    InvalidAddress(String),
+    /// TLS setup failed: unreadable certificates or keys, or an invalid server name.
+    #[error("TLS error: {0}")]
+    Tls(String),
+    #[error("invalid path: {0}")]
+    InvalidPath(String),
+    #[error("device '{0}' not found")]
+    DeviceNotFound(String),
+    #[error("no devices/emulators found")]
+    NoDevices,
+    #[error("more than one device/emulator")]
+    MultipleDevices,
+    /// The device has not accepted this host's key, the message says how to fix it.
+    #[error("{0}")]
+    Unauthorized(String),
+    #[error("device still authorizing")]
+    Authorizing,
+    #[error("device offline")]
+    Offline,
+    #[error("device still connecting")]
+    Connecting,
+    #[error("unknown service: {0}")]
+    UnknownService(String),
+    #[error("{0}")]
+    NoSuchFile(String),
+    #[error("{0}")]
+    PermissionDenied(String),
+    #[error("{0}")]
+    ReadOnlyFs(String),
+    /// No discovered mDNS service matches the instance name.
+    #[error("no mDNS service found for {0}")]
+    MdnsServiceNotFound(String),
+    /// The package manager rejected the APK, with its reason.
+    #[error("install failed: {0}")]
+    InstallFailed(String),
+}
+impl AdbTransportError {
+    /// Maps the payload of a host or sync FAIL reply to a specific error.
+    // https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/transport.cpp
+    pub(crate) fn from_fail(message: &str) -> Self {
+        let message = message.trim();
+        if let Some(serial) = message
+            .strip_prefix("device '")
+            .and_then(|rest| rest.strip_suffix("' not found"))
+        {
+            AdbTransportError::DeviceNotFound(serial.to_string())
+        } else if message.starts_with("no devices") || message.starts_with("no emulators") {
+            AdbTransportError::NoDevices
+        } else if message.starts_with("more than one") {
+            AdbTransportError::MultipleDevices
+        } else if message.starts_with("device unauthorized") {
+            AdbTransportError::Unauthorized(message.to_string())
+        } else if message == "device still authorizing" {
+            AdbTransportError::Authorizing
+        } else if message == "device offline" {
+            AdbTransportError::Offline
+        } else if message == "device still connecting" {
+            AdbTransportError::Connecting
+        } else if message.starts_with("unknown host service") {
+            AdbTransportError::UnknownService(message.to_string())
+        } else if message.contains("No such file or directory") {
+            AdbTransportError::NoSuchFile(message.to_string())
+        } else if message.contains("Permission denied") {
+            AdbTransportError::PermissionDenied(message.to_string())
+        } else if message.contains("Read-only file system") {
+            AdbTransportError::ReadOnlyFs(message.to_string())
+        } else {
+            AdbTransportError::AdbError(message.to_string())
+        }
+    }
+    /// Wraps a failed connect to the server.
+    pub(crate) fn unreachable(err: std::io::Error) -> Self {
+        AdbTransportError::ServerUnreachable(err)
+    }
+}
+impl From<TryFromSliceError> for AdbTransportError {
+    fn from(err: TryFromSliceError) -> Self {
+        AdbTransportError::InvalidResponse("TryFromSlice".to_string(), Some(err.to_string()))
+    }
+}
+impl From<Utf8Error> for AdbTransportError {
+    fn from(err: Utf8Error) -> Self {
+        AdbTransportError::ConversionError(err.to_string())
+    }
+}
+impl From<FromUtf8Error> for AdbTransportError {
+    fn from(err: FromUtf8Error) -> Self {
+        AdbTransportError::ConversionError(err.to_string())
+    }
+}
+impl From<ParseIntError> for AdbTransportError {
+    fn from(err: ParseIntError) -> Self {
+        AdbTransportError::ConversionError(err.to_string())
+    }
+}
+
+#[test]
+fn test_from_fail() {
+    assert!(matches!(
+        AdbTransportError::from_fail("device 'emulator-5554' not found"),
+        AdbTransportError::DeviceNotFound(serial) if serial == "emulator-5554"
+    ));
+    assert!(matches!(
+        AdbTransportError::from_fail("no devices/emulators found"),
+        AdbTransportError::NoDevices
+    ));
+    assert!(matches!(
+        AdbTransportError::from_fail("more than one device/emulator"),
+        AdbTransportError::MultipleDevices
+    ));
+    assert!(matches!(
+        AdbTransportError::from_fail(
+            "device unauthorized.\nThis adb server's $ADB_VENDOR_KEYS is not set"
+        ),
+        AdbTransportError::Unauthorized(_)
+    ));
+    assert!(matches!(
+        AdbTransportError::from_fail("device offline"),
+        AdbTransportError::Offline
+    ));
+    assert!(matches!(
+        AdbTransportError::from_fail("open failed: No such file or directory"),
+        AdbTransportError::NoSuchFile(_)
+    ));
+    assert!(matches!(
+        AdbTransportError::from_fail("couldn't create file: Permission denied"),
+        AdbTransportError::PermissionDenied(_)
+    ));
+    assert!(matches!(
+        AdbTransportError::from_fail("secure_mkdirs() failed: Read-only file system"),
+        AdbTransportError::ReadOnlyFs(_)
+    ));
+    assert!(matches!(
+        AdbTransportError::from_fail("something else"),
+        AdbTransportError::AdbError(message) if message == "something else"
+    ));
+}
@@ -1,172 +1,311 @@
 use crate::transport::transport::AdbTransport;
-use anyhow::Ok;
-use transport::unix_stream_transport::UnixStreamTransport;
+use options::reboot::RebootTarget;
+use options::features::FeatureDetection;
+use options::path::PathPolicy;
+use options::retry::RetryPolicy;
+use options::timeout::Timeouts;
+use options::wait::{WaitState, WaitTransport};
+mod builder;
+mod client;
+pub mod codec;
 mod commands;
+mod device;
+mod error;
+pub mod options;
 pub mod result;
 mod transport;
-mod utils;
 
-#[derive(thiserror::Error, Debug)]
-pub enum AdbTransportError {
-    #[error("IO Error {0:?}")]
-    IoError(std::io::Error),
-    #[error("Adb Transport Error: {0:?}")]
-    AdbError(String),
-    #[error("Invalid Response @{0} :{1:?}")]
-    InvalidResponse(String, Option<String>),
-    #[error("Response Conversion Error: {0:?}")]
-    ConversionError(String),
-    #[error("EOF")]
-    EOF,
-}
-use std::array::TryFromSliceError;
+pub use builder::{AdbTransportsBuilder, DEFAULT_BUFFER_SIZE};
+pub use client::AdbClient;
+pub use commands::service::ReconnectWatch;
+pub use device::DeviceHandle;
+pub use error::{AdbTransportError, Result};
+pub use transport::address::ServerAddress;
+pub use transport::connector::{AbstractConnector, Connector, TcpConnector, UnixConnector};
+#[cfg(feature = "tls")]
+pub use transport::tls::{tls_acceptor, TlsConnector, TlsOptions};
+
+use std::fmt::Display;
 
-impl From<TryFromSliceError> for AdbTransportError {
-    fn from(err: TryFromSliceError) -> Self {
-        AdbTransportError::InvalidResponse("TryFromSlice".to_string(), Some(err.to_string()))
-    }
-}
 // https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/SERVICES.TXT
 pub enum AdbCommand {
     Version,
     Devices,
     ShellExec(String),
+    Exec(String),
     DevicesLong,
     Sync,
     TransportAny,
     TransportSerial(String),
+    TransportId(u64),
+    TransportUsb,
+    TransportLocal,
     TrackDevices,
+    TrackDevicesLong,
+    Reboot(RebootTarget),
+    Root,
+    Unroot,
+    Remount,
+    Tcpip(u16),
+    Usb,
+    EnableVerity,
+    DisableVerity,
+    WaitFor(DeviceSelector, WaitTransport, WaitState),
+    HostQuery(DeviceSelector, HostQuery),
+    Connect(String),
+    Disconnect(Option<String>),
+    Pair(String, String),
+    MdnsCheck,
+    MdnsServices,
+    TrackJdwp,
+    TrackApp,
+    Jdwp(u32),
 }
 
-impl ToString for AdbCommand {
-    fn to_string(&self) -> String {
-        match self {
+impl Display for AdbCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let command = match self {
             AdbCommand::Version => String::from("host:version"),
             AdbCommand::Devices => String::from("host:devices"),
             AdbCommand::ShellExec(cmd) => format!("shell,raw:{}", cmd),
+            AdbCommand::Exec(cmd) => format!("exec:{}", cmd),
             AdbCommand::DevicesLong => String::from("host:devices-l"),
             AdbCommand::Sync => String::from("sync:"),
             AdbCommand::TransportAny => String::from("host:transport-any"),
             AdbCommand::TransportSerial(serial) => format!("host:transport:{}", serial),
+            AdbCommand::TransportId(id) => format!("host:transport-id:{}", id),
+            AdbCommand::TransportUsb => String::from("host:transport-usb"),
+            AdbCommand::TransportLocal => String::from("host:transport-local"),
             AdbCommand::TrackDevices => String::from("host:track-devices"),
-        }
+            AdbCommand::TrackDevicesLong => String::from("host:track-devices-l"),
+            AdbCommand::Reboot(target) => format!("reboot:{}", target),
+            AdbCommand::Root => String::from("root:"),
+            AdbCommand::Unroot => String::from("unroot:"),
+            AdbCommand::Remount => String::from("remount:"),
+            AdbCommand::Tcpip(port) => format!("tcpip:{}", port),
+            AdbCommand::Usb => String::from("usb:"),
+            AdbCommand::EnableVerity => String::from("enable-verity:"),
+            AdbCommand::DisableVerity => String::from("disable-verity:"),
+            AdbCommand::WaitFor(selector, transport, state) => {
+                format!("{}wait-for-{}-{}", selector.host_prefix(), transport, state)
+            }
+            AdbCommand::HostQuery(selector, query) => format!("{}{}", selector.host_prefix(), query),
+            AdbCommand::Connect(address) => format!("host:connect:{}", address),
+            AdbCommand::Disconnect(address) => {
+                format!("host:disconnect:{}", address.as_deref().unwrap_or(""))
+            }
+            AdbCommand::Pair(code, address) => format!("host:pair:{}:{}", code, address),
+            AdbCommand::MdnsCheck => String::from("host:mdns:check"),
+            AdbCommand::MdnsServices => String::from("host:mdns:services"),
+            AdbCommand::TrackJdwp => String::from("track-jdwp"),
+            AdbCommand::TrackApp => String::from("track-app"),
+            AdbCommand::Jdwp(pid) => format!("jdwp:{}", pid),
+        };
+        f.write_str(&command)
     }
 }
-// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/SYNC.TXT
-// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/file_sync_protocol.h
-pub enum AdbSyncModeCommand {
-    Send,
-    Recv,
-    List,
-    Stat,
+/// Picks the device a request goes to, like `adb -s`, `-t`, `-d` and `-e`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DeviceSelector {
+    /// The only connected device, fails when there are several.
+    #[default]
+    Any,
+    Serial(String),
+    TransportId(u64),
+    /// The only device connected over USB.
+    Usb,
+    /// The only emulator or TCP/IP device.
+    Local,
 }
-impl ToString for AdbSyncModeCommand {
-    fn to_string(&self) -> String {
+impl DeviceSelector {
+    /// The request that switches a connection to this device.
+    pub(crate) fn transport_command(&self) -> AdbCommand {
         match self {
-            AdbSyncModeCommand::Send => String::from("SEND"),
-            AdbSyncModeCommand::Recv => String::from("RECV"),
-            AdbSyncModeCommand::List => String::from("LIST"),
-            AdbSyncModeCommand::Stat => String::from("STAT"),
+            DeviceSelector::Any => AdbCommand::TransportAny,
+            DeviceSelector::Serial(serial) => AdbCommand::TransportSerial(serial.clone()),
+            DeviceSelector::TransportId(id) => AdbCommand::TransportId(*id),
+            DeviceSelector::Usb => AdbCommand::TransportUsb,
+            DeviceSelector::Local => AdbCommand::TransportLocal,
+        }
+    }
+    /// The prefix addressing a host service to this device without switching to it.
+    pub(crate) fn host_prefix(&self) -> String {
+        match self {
+            DeviceSelector::Any => String::from("host:"),
+            DeviceSelector::Serial(serial) => format!("host-serial:{}:", serial),
+            DeviceSelector::TransportId(id) => format!("host-transport-id:{}:", id),
+            DeviceSelector::Usb => String::from("host-usb:"),
+            DeviceSelector::Local => String::from("host-local:"),
         }
     }
 }
-impl Into<Vec<u8>> for AdbSyncModeCommand {
-    fn into(self) -> Vec<u8> {
-        self.to_string().into_bytes()
+impl<S: ToString> From<Option<S>> for DeviceSelector {
+    fn from(serial: Option<S>) -> Self {
+        match serial {
+            Some(serial) => DeviceSelector::Serial(serial.to_string()),
+            None => DeviceSelector::Any,
+        }
     }
 }
-enum AdbRespStatus {
-    Okay,
-    Fail(String),
+pub enum HostQuery {
+    GetState,
+    GetSerialNo,
+    GetDevPath,
+    Features,
 }
-// impl TryFrom<Vec<u8>> for AdbRespStatus {
-//     type Error = anyhow::Error;
-
-//     fn try_from(value: Vec<u8>) -> Result<Self, Self::Error> {
-//         let status = value.get(0..4).ok_or(AdbTransportError::InvalidResponse)?;
-//         let status = std::str::from_utf8(status)?;
-//         match status {
-//             "OKAY" => Ok(AdbRespStatus::Okay),
-//             "FAIL" => {
-//                 let length = value.get(4..8).ok_or(AdbTransportError::InvalidResponse)?;
-//                 let length = std::str::from_utf8(length)?;
-//                 let length = usize::from_str_radix(length, 16)?;
-//                 let message = value
-//                     .get(8..length)
-//                     .ok_or(AdbTransportError::InvalidResponse)?;
-//                 let message = std::str::from_utf8(message)?;
-//                 Ok(AdbRespStatus::Fail(message.to_string()))
-//             }
-//             _ => Err(AdbTransportError::InvalidResponse.into()),
-//         }
-//     }
-// }
-impl From<[u8; 4]> for AdbRespStatus {
-    fn from(value: [u8; 4]) -> Self {
-        let status = String::from_utf8(value.to_vec())
-            .unwrap()
-            .to_ascii_lowercase();
-        // println!("{:?}", status);
-
-        match status.as_str() {
-            "okay" => AdbRespStatus::Okay,
-            "fail" => AdbRespStatus::Fail(String::from("")),
-            _ => AdbRespStatus::Fail(String::from("")),
-        }
+impl Display for HostQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let query = match self {
+            HostQuery::GetState => "get-state",
+            HostQuery::GetSerialNo => "get-serialno",
+            HostQuery::GetDevPath => "get-devpath",
+            HostQuery::Features => "features",
+        };
+        write!(f, "{}", query)
     }
 }
+/// What the session connection of an `AdbTransports` is ready for. Requests that need
+/// another state reconnect first, so a session never runs against the wrong device.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SessionState {
+    /// Connected, nothing sent yet.
+    #[default]
+    Idle,
+    /// A host service such as `wait-for` is answering on the connection.
+    HostMode,
+    /// Switched to a device, ready to open one service on it.
+    Transport(DeviceSelector),
+    /// A sync session is open on a device, it serves any number of file requests.
+    SyncMode(DeviceSelector),
+    /// A service owns the stream or a request failed, only a reconnect makes it usable.
+    Consumed,
+}
 pub struct AdbTransports {
-    serial_set: bool,
+    state: SessionState,
     transports: Box<dyn AdbTransport>,
-    json: bool,
-    is_sync: bool,
+    selector: DeviceSelector,
+    retry: Option<RetryPolicy>,
+    path_policy: PathPolicy,
+    buffer_size: usize,
+    features: FeatureDetection,
 }
 impl AdbTransports {
-    pub async fn new(addr: String, json: bool) -> anyhow::Result<Self> {
-        let transports: Box<dyn AdbTransport> = match addr {
-            addr if addr.starts_with("tcp:") => {
-                let addr = addr.trim_start_matches("tcp:");
-                Box::new(transport::tcp_transport::TcpStreamTransport::new(addr.to_string()).await?)
-            }
-            addr if (addr.starts_with("/") || addr.starts_with("./")) => Box::new(
-                transport::unix_stream_transport::UnixStreamTransport::new(addr.to_string())
-                    .await?,
-            ),
-            _ => {
-                Box::new(transport::tcp_transport::TcpStreamTransport::new(addr.to_string()).await?)
-            }
-        };
-        Ok(Self {
-            transports,
-            json,
-            serial_set: false,
-            is_sync: false,
+    /// Connects to the server at `addr`, in the grammar of `ADB_SERVER_SOCKET`.
+    pub async fn new(addr: String) -> Result<Self> {
+        Self::from_address(addr.parse()?).await
+    }
+    pub async fn from_address(address: ServerAddress) -> Result<Self> {
+        AdbTransportsBuilder::new().address(address).build().await
+    }
+    /// Talks to the server over the streams `connector` opens instead of an address.
+    pub async fn with_connector<C>(connector: C) -> Result<Self>
+    where
+        C: Connector + 'static,
+    {
+        AdbTransportsBuilder::new().connector(connector).build().await
+    }
+    pub fn builder() -> AdbTransportsBuilder {
+        AdbTransportsBuilder::new()
+    }
+    /// The device set with `AdbTransportsBuilder::device`, any device by default.
+    pub fn default_selector(&self) -> DeviceSelector {
+        self.selector.clone()
+    }
+    pub fn state(&self) -> &SessionState {
+        &self.state
+    }
+    /// A second session on its own connection, configured like this one.
+    pub(crate) async fn fork(&self) -> Result<AdbTransports> {
+        Ok(AdbTransports {
+            state: SessionState::Idle,
+            transports: self.transports.duplicate().await?,
+            selector: self.selector.clone(),
+            retry: self.retry.clone(),
+            path_policy: self.path_policy.clone(),
+            buffer_size: self.buffer_size,
+            features: self.features.clone(),
         })
     }
-    pub async fn may_set_serial<S: ToString>(&mut self, serial: Option<S>) -> anyhow::Result<()> {
-        if !self.serial_set {
-            let transport_ = match serial {
-                Some(serial) => AdbCommand::TransportSerial(serial.to_string()),
-                None => AdbCommand::TransportAny,
-            };
-            self.transports.send_command(transport_, false).await?;
-            self.serial_set = true;
+    /// Switches the session to `selector`, reconnecting unless it is already there.
+    pub async fn may_set_device(&mut self, selector: DeviceSelector) -> Result<()> {
+        match &self.state {
+            SessionState::Transport(current) if *current == selector => return Ok(()),
+            SessionState::Idle => {}
+            _ => self.new_connection().await?,
+        }
+        self.send_session(selector.transport_command()).await?;
+        self.state = SessionState::Transport(selector);
+        Ok(())
+    }
+    /// Opens a sync session on `selector`, keeping one that is already open there.
+    pub async fn may_set_sync(&mut self, selector: DeviceSelector) -> Result<()> {
+        if let SessionState::SyncMode(current) = &self.state {
+            if *current == selector {
+                return Ok(());
+            }
         }
+        self.may_set_device(selector.clone()).await?;
+        self.send_session(AdbCommand::Sync).await?;
+        self.state = SessionState::SyncMode(selector);
         Ok(())
     }
-    pub async fn may_set_sync(&mut self) -> anyhow::Result<()> {
-        if !self.is_sync {
-            self.transports
-                .send_command(AdbCommand::Sync, false)
-                .await?;
-            self.is_sync = true;
+    /// Opens `command` on `selector`, the service then owns the stream.
+    pub(crate) async fn open_service(
+        &mut self,
+        selector: DeviceSelector,
+        command: AdbCommand,
+    ) -> Result<()> {
+        self.may_set_device(selector).await?;
+        self.send_session(command).await
+    }
+    /// Sends a host service that answers on the session connection, such as `wait-for`.
+    pub(crate) async fn open_host_service(&mut self, command: AdbCommand) -> Result<()> {
+        if self.state != SessionState::Idle {
+            self.new_connection().await?;
         }
+        self.send_session(command).await?;
+        self.state = SessionState::HostMode;
         Ok(())
     }
-    pub async fn new_connection(&mut self) -> anyhow::Result<()> {
-        self.transports.reconnect().await?;
-        self.serial_set = false;
-        self.is_sync = false;
+    /// Sends a request on the session connection. It stays consumed unless the caller
+    /// moves it to the state the request leads to.
+    async fn send_session(&mut self, command: AdbCommand) -> Result<()> {
+        self.state = SessionState::Consumed;
+        self.transports.send_command(command, false).await?;
         Ok(())
     }
+    /// Marks the session unusable when `result` failed, the server may have closed it.
+    pub(crate) fn consume_on_error<T>(&mut self, result: Result<T>) -> Result<T> {
+        if result.is_err() {
+            self.state = SessionState::Consumed;
+        }
+        result
+    }
+    /// Limits connects, requests and reads from now on, on this and later connections.
+    pub fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.transports.set_timeouts(timeouts);
+    }
+    pub async fn new_connection(&mut self) -> Result<()> {
+        self.state = SessionState::Consumed;
+        let mut failures = 0;
+        loop {
+            let err = match self.transports.reconnect().await {
+                Ok(()) => {
+                    self.state = SessionState::Idle;
+                    return Ok(());
+                }
+                Err(err) => err,
+            };
+            failures += 1;
+            let delay = self
+                .retry
+                .as_ref()
+                .and_then(|policy| policy.reconnect_delay(failures, &err));
+            match delay {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(err),
+            }
+        }
+    }
 }
+
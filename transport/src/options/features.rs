@@ -0,0 +1,11 @@
+/// How transfers learn which protocol versions the device supports.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FeatureDetection {
+    /// Never asks, transfers use the original sync protocol.
+    #[default]
+    Legacy,
+    /// Asks the server for the device features before a transfer, like adb does.
+    Query,
+    /// Takes these as the device features without asking.
+    Assume(Vec<String>),
+}
@@ -0,0 +1,278 @@
+use std::fmt::Display;
+use std::time::Duration;
+
+use base64::Engine;
+
+// https://developer.android.com/reference/android/view/KeyEvent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyCode {
+    Home,
+    Back,
+    Call,
+    EndCall,
+    DpadUp,
+    DpadDown,
+    DpadLeft,
+    DpadRight,
+    DpadCenter,
+    VolumeUp,
+    VolumeDown,
+    Power,
+    Camera,
+    Tab,
+    Space,
+    Enter,
+    Del,
+    Menu,
+    Search,
+    MediaPlayPause,
+    MediaNext,
+    MediaPrevious,
+    PageUp,
+    PageDown,
+    Escape,
+    ForwardDel,
+    MoveHome,
+    MoveEnd,
+    VolumeMute,
+    AppSwitch,
+    Sleep,
+    Wakeup,
+    /// Any keycode not listed above, by its numeric value.
+    Other(u32),
+}
+impl KeyCode {
+    pub fn code(&self) -> u32 {
+        match self {
+            KeyCode::Home => 3,
+            KeyCode::Back => 4,
+            KeyCode::Call => 5,
+            KeyCode::EndCall => 6,
+            KeyCode::DpadUp => 19,
+            KeyCode::DpadDown => 20,
+            KeyCode::DpadLeft => 21,
+            KeyCode::DpadRight => 22,
+            KeyCode::DpadCenter => 23,
+            KeyCode::VolumeUp => 24,
+            KeyCode::VolumeDown => 25,
+            KeyCode::Power => 26,
+            KeyCode::Camera => 27,
+            KeyCode::Tab => 61,
+            KeyCode::Space => 62,
+            KeyCode::Enter => 66,
+            KeyCode::Del => 67,
+            KeyCode::Menu => 82,
+            KeyCode::Search => 84,
+            KeyCode::MediaPlayPause => 85,
+            KeyCode::MediaNext => 87,
+            KeyCode::MediaPrevious => 88,
+            KeyCode::PageUp => 92,
+            KeyCode::PageDown => 93,
+            KeyCode::Escape => 111,
+            KeyCode::ForwardDel => 112,
+            KeyCode::MoveHome => 122,
+            KeyCode::MoveEnd => 123,
+            KeyCode::VolumeMute => 164,
+            KeyCode::AppSwitch => 187,
+            KeyCode::Sleep => 223,
+            KeyCode::Wakeup => 224,
+            KeyCode::Other(code) => *code,
+        }
+    }
+}
+impl Display for KeyCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.code())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputEvent {
+    Tap {
+        x: u32,
+        y: u32,
+    },
+    Swipe {
+        from: (u32, u32),
+        to: (u32, u32),
+        duration: Option<Duration>,
+    },
+    KeyEvent {
+        code: KeyCode,
+        long_press: bool,
+    },
+    LongPress {
+        x: u32,
+        y: u32,
+        duration: Duration,
+    },
+    Text(String),
+}
+
+// Intent actions of ADBKeyBoard (https://github.com/senzhk/ADBKeyBoard), which has to be
+// installed and selected as IME for non-ASCII text.
+pub const IME_BROADCAST_B64: &str = "ADB_INPUT_B64";
+
+impl InputEvent {
+    /// Builds the device-side shell command for this event.
+    pub fn to_command(&self) -> String {
+        match self {
+            InputEvent::Tap { x, y } => format!("input tap {} {}", x, y),
+            InputEvent::Swipe { from, to, duration } => {
+                let mut cmd = format!("input swipe {} {} {} {}", from.0, from.1, to.0, to.1);
+                if let Some(duration) = duration {
+                    cmd.push_str(&format!(" {}", duration.as_millis()));
+                }
+                cmd
+            }
+            InputEvent::KeyEvent { code, long_press } => {
+                if *long_press {
+                    format!("input keyevent --longpress {}", code)
+                } else {
+                    format!("input keyevent {}", code)
+                }
+            }
+            // A swipe that does not move is how `input` expresses a long press.
+            InputEvent::LongPress { x, y, duration } => {
+                format!(
+                    "input swipe {} {} {} {} {}",
+                    x,
+                    y,
+                    x,
+                    y,
+                    duration.as_millis()
+                )
+            }
+            InputEvent::Text(text) => text_command(text),
+        }
+    }
+}
+
+/// `input text` can only type what the virtual keyboard map knows, i.e. printable ASCII.
+fn is_input_text_safe(text: &str) -> bool {
+    text.chars()
+        .all(|c| c.is_ascii_graphic() || c == ' ' || c == '\n')
+}
+
+fn text_command(text: &str) -> String {
+    if !is_input_text_safe(text) {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+        return format!("am broadcast -a {} --es msg {}", IME_BROADCAST_B64, encoded);
+    }
+    // `input text` cannot type a newline, so lines are joined with ENTER key events.
+    let mut cmds = Vec::new();
+    for (index, line) in text.split('\n').enumerate() {
+        if index > 0 {
+            cmds.push(format!("input keyevent {}", KeyCode::Enter));
+        }
+        for chunk in split_literal_spaces(line) {
+            cmds.push(format!("input text {}", escape_input_text(chunk)));
+        }
+    }
+    cmds.join(";")
+}
+
+/// `input text` reads every `%s` as a space, so a literal `%s` is typed in two calls,
+/// one ending with the `%` and the next starting with the `s`.
+fn split_literal_spaces(line: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = line;
+    while let Some(at) = rest.find("%s") {
+        chunks.push(&rest[..at + 1]);
+        rest = &rest[at + 1..];
+    }
+    if !rest.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+/// Escapes `text` for `input text`: spaces become `%s`, which `input` turns back into
+/// spaces, and the rest is single-quoted so the device shell passes it through verbatim.
+pub fn escape_input_text(text: &str) -> String {
+    let text = text.replace(' ', "%s").replace('\'', "'\\''");
+    format!("'{}'", text)
+}
+
+/// Several input events sent through a single shell invocation.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InputBatch {
+    pub events: Vec<InputEvent>,
+}
+impl InputBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn push(mut self, event: InputEvent) -> Self {
+        self.events.push(event);
+        self
+    }
+    pub fn tap(self, x: u32, y: u32) -> Self {
+        self.push(InputEvent::Tap { x, y })
+    }
+    pub fn swipe(self, from: (u32, u32), to: (u32, u32), duration: Option<Duration>) -> Self {
+        self.push(InputEvent::Swipe { from, to, duration })
+    }
+    pub fn keyevent(self, code: KeyCode) -> Self {
+        self.push(InputEvent::KeyEvent {
+            code,
+            long_press: false,
+        })
+    }
+    pub fn long_press(self, x: u32, y: u32, duration: Duration) -> Self {
+        self.push(InputEvent::LongPress { x, y, duration })
+    }
+    pub fn text<S: ToString>(self, text: S) -> Self {
+        self.push(InputEvent::Text(text.to_string()))
+    }
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+    pub fn to_command(&self) -> String {
+        self.events
+            .iter()
+            .map(InputEvent::to_command)
+            .collect::<Vec<String>>()
+            .join(";")
+    }
+}
+
+#[test]
+fn test_input_text_escape() {
+    assert_eq!(
+        InputEvent::Text("hello world".to_string()).to_command(),
+        "input text 'hello%sworld'"
+    );
+    assert_eq!(
+        InputEvent::Text("it's $HOME; rm -rf *".to_string()).to_command(),
+        r#"input text 'it'\''s%s$HOME;%srm%s-rf%s*'"#
+    );
+    assert_eq!(
+        InputEvent::Text("a\n\nb".to_string()).to_command(),
+        "input text 'a';input keyevent 66;input keyevent 66;input text 'b'"
+    );
+    assert_eq!(
+        InputEvent::Text("100%sure, 50% sold".to_string()).to_command(),
+        "input text '100%';input text 'sure,%s50%%ssold'"
+    );
+    assert_eq!(
+        InputEvent::Text("%s%s".to_string()).to_command(),
+        "input text '%';input text 's%';input text 's'"
+    );
+    assert_eq!(
+        InputEvent::Text("héllo".to_string()).to_command(),
+        "am broadcast -a ADB_INPUT_B64 --es msg aMOpbGxv"
+    );
+}
+#[test]
+fn test_input_batch() {
+    let batch = InputBatch::new()
+        .tap(100, 200)
+        .swipe((0, 0), (100, 100), Some(Duration::from_millis(300)))
+        .long_press(50, 60, Duration::from_secs(1))
+        .keyevent(KeyCode::Home);
+    assert_eq!(
+        batch.to_command(),
+        "input tap 100 200;input swipe 0 0 100 100 300;input swipe 50 60 50 60 1000;input keyevent 3"
+    );
+}
@@ -0,0 +1,8 @@
+pub mod features;
+pub mod input;
+pub mod path;
+pub mod reboot;
+pub mod retry;
+pub mod screenrecord;
+pub mod timeout;
+pub mod wait;
@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use crate::{AdbTransportError, Result};
+
+/// Device directories `mkdir` may create directories in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathPolicy {
+    /// Any well-formed path.
+    Any,
+    /// Relative paths, and absolute paths under one of these directories. Paths with a
+    /// `..` segment are refused, they could climb out of it.
+    Within(Vec<String>),
+}
+impl Default for PathPolicy {
+    fn default() -> Self {
+        PathPolicy::Within(vec![String::from("/data/local/tmp")])
+    }
+}
+impl PathPolicy {
+    /// Rejects malformed paths, whatever the policy.
+    pub(crate) fn validate(path: &str) -> Result<()> {
+        let reason = if path.len() > 1024 {
+            "Path too long"
+        } else if path.is_empty() {
+            "Path is empty"
+        } else if path.contains('\0') {
+            "Path contains null byte"
+        } else if path.contains("//") {
+            "Path contains double slash"
+        } else if path == "/" {
+            "Path is root"
+        } else {
+            return Ok(());
+        };
+        Err(AdbTransportError::InvalidPath(String::from(reason)))
+    }
+    /// Rejects malformed paths and paths outside the policy.
+    pub(crate) fn check(&self, path: &str) -> Result<()> {
+        Self::validate(path)?;
+        let allowed = match self {
+            PathPolicy::Any => true,
+            PathPolicy::Within(dirs) => {
+                !path.split('/').any(|segment| segment == "..")
+                    && (!path.starts_with('/')
+                        || dirs.iter().any(|dir| Path::new(path).starts_with(dir)))
+            }
+        };
+        if !allowed {
+            return Err(AdbTransportError::InvalidPath(format!(
+                "{} is not allowed",
+                path
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_path_policy() {
+    let policy = PathPolicy::default();
+    assert!(policy.check("/data/local/tmp/screens").is_ok());
+    assert!(policy.check("screens").is_ok());
+    assert!(matches!(
+        policy.check("/sdcard/screens"),
+        Err(AdbTransportError::InvalidPath(_))
+    ));
+    // Whole components only, and no climbing out of the directory.
+    assert!(policy.check("/data/local/tmp").is_ok());
+    assert!(policy.check("/data/local/tmpfoo").is_err());
+    assert!(policy.check("/data/local/tmp/../../system").is_err());
+    assert!(policy.check("../system").is_err());
+    assert!(policy.check("/data/local/tmp/a..b").is_ok());
+    assert!(PathPolicy::Any.check("/sdcard/screens").is_ok());
+    assert!(PathPolicy::Any.check("/sdcard//screens").is_err());
+    assert!(PathPolicy::Any.check("/").is_err());
+}
@@ -0,0 +1,25 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RebootTarget {
+    #[default]
+    System,
+    Bootloader,
+    Recovery,
+    Sideload,
+    SideloadAutoReboot,
+    Fastboot,
+}
+impl Display for RebootTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let target = match self {
+            RebootTarget::System => "",
+            RebootTarget::Bootloader => "bootloader",
+            RebootTarget::Recovery => "recovery",
+            RebootTarget::Sideload => "sideload",
+            RebootTarget::SideloadAutoReboot => "sideload-auto-reboot",
+            RebootTarget::Fastboot => "fastboot",
+        };
+        write!(f, "{}", target)
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use crate::AdbTransportError;
+
+/// Exponential backoff between reconnection attempts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: u32,
+    /// Gives up after this many failed attempts, retries forever when unset.
+    pub max_attempts: Option<u32>,
+}
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2,
+            max_attempts: None,
+        }
+    }
+}
+impl RetryPolicy {
+    /// The delay before the given attempt, counted from zero.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.checked_pow(attempt).unwrap_or(u32::MAX);
+        self.initial_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+    pub fn should_retry(&self, attempt: u32) -> bool {
+        self.max_attempts
+            .is_none_or(|max_attempts| attempt < max_attempts)
+    }
+    /// The delay before connecting again after `failures` failed attempts ending in
+    /// `err`, `None` when `err` is not worth retrying or the attempts are used up.
+    pub(crate) fn reconnect_delay(&self, failures: u32, err: &AdbTransportError) -> Option<Duration> {
+        match err {
+            AdbTransportError::ServerUnreachable(_) | AdbTransportError::Timeout(_)
+                if self.should_retry(failures) =>
+            {
+                Some(self.delay(failures.saturating_sub(1)))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_retry_delay() {
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.delay(0), Duration::from_millis(100));
+    assert_eq!(policy.delay(3), Duration::from_millis(800));
+    assert_eq!(policy.delay(7), Duration::from_secs(10));
+    assert_eq!(policy.delay(64), Duration::from_secs(10));
+    assert!(policy.should_retry(1000));
+    let policy = RetryPolicy {
+        max_attempts: Some(3),
+        ..Default::default()
+    };
+    assert!(policy.should_retry(2));
+    assert!(!policy.should_retry(3));
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+// https://cs.android.com/android/platform/superproject/main/+/main:frameworks/av/cmds/screenrecord/screenrecord.cpp
+// screenrecord refuses anything above three minutes.
+pub const MAX_TIME_LIMIT: Duration = Duration::from_secs(180);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScreenRecordOptions {
+    /// Video size as (width, height), defaults to the display resolution.
+    pub size: Option<(u32, u32)>,
+    /// Video bit rate in bits per second.
+    pub bit_rate: Option<u32>,
+    /// Maximum recording time, capped at `MAX_TIME_LIMIT` by the device.
+    pub time_limit: Option<Duration>,
+}
+
+impl ScreenRecordOptions {
+    pub fn size(mut self, width: u32, height: u32) -> Self {
+        self.size = Some((width, height));
+        self
+    }
+    pub fn bit_rate(mut self, bit_rate: u32) -> Self {
+        self.bit_rate = Some(bit_rate);
+        self
+    }
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+    /// Builds the device-side command line, writing raw H.264 to stdout.
+    pub fn to_command(&self) -> String {
+        let mut cmd = String::from("screenrecord --output-format=h264");
+        if let Some((width, height)) = self.size {
+            cmd.push_str(&format!(" --size {}x{}", width, height));
+        }
+        if let Some(bit_rate) = self.bit_rate {
+            cmd.push_str(&format!(" --bit-rate {}", bit_rate));
+        }
+        if let Some(time_limit) = self.time_limit {
+            let secs = time_limit.min(MAX_TIME_LIMIT).as_secs().max(1);
+            cmd.push_str(&format!(" --time-limit {}", secs));
+        }
+        cmd.push_str(" -");
+        cmd
+    }
+}
+
+#[test]
+fn test_screenrecord_command() {
+    assert_eq!(
+        ScreenRecordOptions::default().to_command(),
+        "screenrecord --output-format=h264 -"
+    );
+    let options = ScreenRecordOptions::default()
+        .size(1280, 720)
+        .bit_rate(4_000_000)
+        .time_limit(Duration::from_secs(600));
+    assert_eq!(
+        options.to_command(),
+        "screenrecord --output-format=h264 --size 1280x720 --bit-rate 4000000 --time-limit 180 -"
+    );
+}
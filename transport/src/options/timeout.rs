@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+/// Limits applied to every connection, `None` waits forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Opening a connection to the server, reconnects included.
+    pub connect: Option<Duration>,
+    /// Sending a request until its OKAY or FAIL status, and a host reply, arrives.
+    pub request: Option<Duration>,
+    /// A single read waiting for data. Streams that may sit quiet, like device
+    /// tracking or `logcat`, fail too when this is set.
+    pub idle_read: Option<Duration>,
+    /// A single write waiting for the other end to take data, e.g. a device that
+    /// stopped reading an upload.
+    pub write: Option<Duration>,
+}
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect: Some(Duration::from_secs(10)),
+            request: Some(Duration::from_secs(30)),
+            idle_read: None,
+            write: Some(Duration::from_secs(30)),
+        }
+    }
+}
+impl Timeouts {
+    /// No limits at all, how connections behaved before timeouts existed.
+    pub fn none() -> Self {
+        Self {
+            connect: None,
+            request: None,
+            idle_read: None,
+            write: None,
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::AdbTransportError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WaitTransport {
+    #[default]
+    Any,
+    Usb,
+    Local,
+}
+impl Display for WaitTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let transport = match self {
+            WaitTransport::Any => "any",
+            WaitTransport::Usb => "usb",
+            WaitTransport::Local => "local",
+        };
+        write!(f, "{}", transport)
+    }
+}
+impl FromStr for WaitTransport {
+    type Err = AdbTransportError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "any" => Ok(WaitTransport::Any),
+            "usb" => Ok(WaitTransport::Usb),
+            "local" => Ok(WaitTransport::Local),
+            _ => Err(AdbTransportError::ConversionError(format!("unknown transport: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WaitState {
+    #[default]
+    Device,
+    Recovery,
+    Rescue,
+    Sideload,
+    Bootloader,
+    Disconnect,
+}
+impl Display for WaitState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self {
+            WaitState::Device => "device",
+            WaitState::Recovery => "recovery",
+            WaitState::Rescue => "rescue",
+            WaitState::Sideload => "sideload",
+            WaitState::Bootloader => "bootloader",
+            WaitState::Disconnect => "disconnect",
+        };
+        write!(f, "{}", state)
+    }
+}
+impl FromStr for WaitState {
+    type Err = AdbTransportError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "device" => Ok(WaitState::Device),
+            "recovery" => Ok(WaitState::Recovery),
+            "rescue" => Ok(WaitState::Rescue),
+            "sideload" => Ok(WaitState::Sideload),
+            "bootloader" => Ok(WaitState::Bootloader),
+            "disconnect" => Ok(WaitState::Disconnect),
+            _ => Err(AdbTransportError::ConversionError(format!("unknown state: {}", s))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WaitForOptions {
+    pub transport: WaitTransport,
+    pub state: WaitState,
+    /// Gives up after this long, waits forever when unset.
+    pub timeout: Option<Duration>,
+    /// Also waits for `sys.boot_completed=1` once the device is in `WaitState::Device`.
+    pub boot_completed: bool,
+}
@@ -0,0 +1,66 @@
+// Status lines printed by `bugreportz -p`.
+// https://cs.android.com/android/platform/superproject/main/+/main:frameworks/native/cmds/bugreportz/readme.md
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum BugreportEvent {
+    /// "BEGIN:<path>", the zip that will be written on the device
+    Begin(String),
+    /// "PROGRESS:<current>/<total>"
+    Progress { current: u64, total: u64 },
+    /// "OK:<path>", the finished zip on the device
+    Ok(String),
+    /// "FAIL:<message>"
+    Fail(String),
+}
+impl BugreportEvent {
+    /// Parses one status line, other output of bugreportz is ignored.
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let line = line.trim();
+        if let Some(path) = line.strip_prefix("BEGIN:") {
+            Some(BugreportEvent::Begin(path.to_string()))
+        } else if let Some(progress) = line.strip_prefix("PROGRESS:") {
+            let (current, total) = progress.split_once('/')?;
+            Some(BugreportEvent::Progress {
+                current: current.parse().ok()?,
+                total: total.parse().ok()?,
+            })
+        } else if let Some(path) = line.strip_prefix("OK:") {
+            Some(BugreportEvent::Ok(path.to_string()))
+        } else {
+            line.strip_prefix("FAIL:")
+                .map(|message| BugreportEvent::Fail(message.to_string()))
+        }
+    }
+}
+
+#[test]
+fn test_bugreport_events() {
+    assert_eq!(
+        BugreportEvent::parse("BEGIN:/bugreports/bugreport-sdk-2024.zip"),
+        Some(BugreportEvent::Begin(
+            "/bugreports/bugreport-sdk-2024.zip".to_string()
+        ))
+    );
+    assert_eq!(
+        BugreportEvent::parse("PROGRESS:150/1000\r"),
+        Some(BugreportEvent::Progress {
+            current: 150,
+            total: 1000
+        })
+    );
+    assert_eq!(
+        BugreportEvent::parse("OK:/bugreports/bugreport-sdk-2024.zip"),
+        Some(BugreportEvent::Ok(
+            "/bugreports/bugreport-sdk-2024.zip".to_string()
+        ))
+    );
+    assert_eq!(
+        BugreportEvent::parse("FAIL:Could not create zip file"),
+        Some(BugreportEvent::Fail(
+            "Could not create zip file".to_string()
+        ))
+    );
+    assert_eq!(BugreportEvent::parse("PROGRESS:abc"), None);
+    assert_eq!(BugreportEvent::parse("dumpstate output"), None);
+}
@@ -0,0 +1,159 @@
+use std::fmt::Display;
+
+// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/client/adb_wifi.cpp
+// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/socket_spec.cpp
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectOutcome {
+    /// "connected to <address>"
+    Connected(String),
+    /// "already connected to <address>"
+    AlreadyConnected(String),
+    /// "failed to authenticate to <address>", the device has to accept the key first.
+    FailedToAuthenticate(String),
+    /// Any other reply, e.g. "failed to connect to '<address>': Connection refused".
+    Failed(String),
+}
+impl From<&str> for ConnectOutcome {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        if let Some(address) = value.strip_prefix("connected to ") {
+            ConnectOutcome::Connected(address.to_string())
+        } else if let Some(address) = value.strip_prefix("already connected to ") {
+            ConnectOutcome::AlreadyConnected(address.to_string())
+        } else if let Some(address) = value.strip_prefix("failed to authenticate to ") {
+            ConnectOutcome::FailedToAuthenticate(address.to_string())
+        } else {
+            ConnectOutcome::Failed(value.to_string())
+        }
+    }
+}
+impl ConnectOutcome {
+    pub fn is_connected(&self) -> bool {
+        matches!(
+            self,
+            ConnectOutcome::Connected(_) | ConnectOutcome::AlreadyConnected(_)
+        )
+    }
+}
+impl Display for ConnectOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConnectOutcome::Connected(address) => write!(f, "connected to {}", address),
+            ConnectOutcome::AlreadyConnected(address) => {
+                write!(f, "already connected to {}", address)
+            }
+            ConnectOutcome::FailedToAuthenticate(address) => {
+                write!(f, "failed to authenticate to {}", address)
+            }
+            ConnectOutcome::Failed(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisconnectOutcome {
+    /// "disconnected <address>"
+    Disconnected(String),
+    /// "disconnected everything"
+    DisconnectedAll,
+}
+impl From<&str> for DisconnectOutcome {
+    fn from(value: &str) -> Self {
+        match value.trim() {
+            "disconnected everything" => DisconnectOutcome::DisconnectedAll,
+            value => DisconnectOutcome::Disconnected(
+                value.trim_start_matches("disconnected ").to_string(),
+            ),
+        }
+    }
+}
+impl Display for DisconnectOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DisconnectOutcome::Disconnected(address) => write!(f, "disconnected {}", address),
+            DisconnectOutcome::DisconnectedAll => write!(f, "disconnected everything"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairOutcome {
+    /// "Successfully paired to <address> [guid=<guid>]"
+    Paired {
+        address: String,
+        guid: Option<String>,
+    },
+    /// "Failed: <reason>"
+    Failed(String),
+}
+impl From<&str> for PairOutcome {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        match value.strip_prefix("Successfully paired to ") {
+            Some(paired) => {
+                let (address, guid) = match paired.split_once(" [guid=") {
+                    Some((address, guid)) => (
+                        address.to_string(),
+                        Some(guid.trim_end_matches(']').to_string()),
+                    ),
+                    None => (paired.to_string(), None),
+                };
+                PairOutcome::Paired { address, guid }
+            }
+            None => PairOutcome::Failed(value.trim_start_matches("Failed: ").to_string()),
+        }
+    }
+}
+impl Display for PairOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PairOutcome::Paired {
+                address,
+                guid: Some(guid),
+            } => write!(f, "Successfully paired to {} [guid={}]", address, guid),
+            PairOutcome::Paired {
+                address,
+                guid: None,
+            } => write!(f, "Successfully paired to {}", address),
+            PairOutcome::Failed(reason) => write!(f, "Failed: {}", reason),
+        }
+    }
+}
+
+#[test]
+fn test_connect_outcome() {
+    assert_eq!(
+        ConnectOutcome::from("connected to 192.168.1.20:5555"),
+        ConnectOutcome::Connected("192.168.1.20:5555".to_string())
+    );
+    assert_eq!(
+        ConnectOutcome::from("already connected to 192.168.1.20:5555"),
+        ConnectOutcome::AlreadyConnected("192.168.1.20:5555".to_string())
+    );
+    assert_eq!(
+        ConnectOutcome::from("failed to authenticate to 192.168.1.20:5555"),
+        ConnectOutcome::FailedToAuthenticate("192.168.1.20:5555".to_string())
+    );
+    assert_eq!(
+        ConnectOutcome::from("failed to connect to '192.168.1.20:5555': Connection refused"),
+        ConnectOutcome::Failed(
+            "failed to connect to '192.168.1.20:5555': Connection refused".to_string()
+        )
+    );
+    assert_eq!(
+        DisconnectOutcome::from("disconnected everything"),
+        DisconnectOutcome::DisconnectedAll
+    );
+    assert_eq!(
+        PairOutcome::from("Successfully paired to 192.168.1.20:37099 [guid=adb-1A2B3C-xyz]"),
+        PairOutcome::Paired {
+            address: "192.168.1.20:37099".to_string(),
+            guid: Some("adb-1A2B3C-xyz".to_string()),
+        }
+    );
+    assert_eq!(
+        PairOutcome::from("Failed: Wrong password or connection was dropped."),
+        PairOutcome::Failed("Wrong password or connection was dropped.".to_string())
+    );
+}
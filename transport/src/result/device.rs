@@ -1,24 +1,18 @@
 use std::fmt::Display;
 
-use anyhow::Result;
+use crate::{AdbTransportError, Result};
 use nom::{
-    bytes::complete::{tag, take_until, take_while1},
-    character::complete::{multispace1, space1, digit1, one_of, space0},
+    bytes::complete::{tag, take_while1},
+    character::complete::{multispace1, space1, digit1, space0},
     combinator::{opt, peek},
     error::ParseError,
-    number::complete::{u64, u32},
     sequence::{preceded, tuple, terminated},
-    IResult, branch::alt, Parser,
+    IResult, branch::alt,
 };
 use nom::combinator::not;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
-pub enum Devices {
-    Devices(Vec<Device>),
-    Raw(String),
-}
-#[derive(Debug, PartialEq,Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DeviceState {
     Connecting,   // Haven't received a response from the device yet.
     Authorizing,  // Authorizing with keys from ADB_VENDOR_KEYS.
@@ -34,7 +28,7 @@ pub enum DeviceState {
     Rescue,     // Device running Android OS Rescue mode (minadbd rescue mode).
 }
 impl DeviceState {
-    fn parse_device_state(input: &str) -> IResult<&str, DeviceState> {
+    pub(crate) fn parse_device_state(input: &str) -> IResult<&str, DeviceState> {
         let (input, state) = alt((
             tag("connecting"),
             tag("authorizing"),
@@ -69,9 +63,9 @@ impl DeviceState {
         Ok((input, state))
     }
 }
-impl ToString for DeviceState {
-    fn to_string(&self) -> String {
-        match self {
+impl Display for DeviceState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = match self {
             DeviceState::Connecting => String::from("connecting"),
             DeviceState::Authorizing => String::from("authorizing"),
             DeviceState::Unauthorized => String::from("unauthorized"),
@@ -84,11 +78,11 @@ impl ToString for DeviceState {
             DeviceState::Recovery => String::from("recovery"),
             DeviceState::Sideload => String::from("sideload"),
             DeviceState::Rescue => String::from("rescue"),
-        }
+        };
+        f.write_str(&state)
     }
-    
 }
-#[derive(Debug,Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Device {
     pub serial: String,
     pub state: DeviceState,
@@ -99,9 +93,9 @@ pub struct Device {
     pub transport_id: Option<u64>,
 }
 impl TryFrom<&str> for Device {
-    type Error = anyhow::Error;
+    type Error = AdbTransportError;
     fn try_from(value: &str) -> Result<Self> {
-        Self::nom_parse(value).map_err(|err| anyhow::Error::msg(err.to_string()))
+        Self::nom_parse(value).map_err(|err| AdbTransportError::ConversionError(err.to_string()))
     }
 }
 #[inline]
@@ -109,6 +103,14 @@ fn is_not_whitespace(c: char) -> bool {
     !c.is_whitespace()
 }
 impl Device {
+    /// Parses a device list as sent by `host:devices`, `host:devices-l` and the tracking
+    /// services, one device per line.
+    pub(crate) fn parse_list(value: &str) -> Vec<Device> {
+        value
+            .lines()
+            .filter_map(|line| Device::nom_parse(line.trim()).ok())
+            .collect()
+    }
     fn nom_parse(value: &str) -> Result<Device, nom::Err<nom::error::Error<&str>>> {
         let (value, serial) = take_while1(is_not_whitespace)(value)?;
         let (value, _) = space1(value)?;
@@ -131,7 +133,7 @@ impl Device {
             model: model.map(|s| s.to_string()),
             device: device.map(|s| s.to_string()),
             devpath: devpath.map(|s| s.to_string()),
-            transport_id: transport_id.map(|s| s.parse::<u64>().ok()).flatten(),
+            transport_id: transport_id.and_then(|s| s.parse::<u64>().ok()),
         })
 
     }
@@ -188,4 +190,13 @@ fn test_devices(){
     assert_eq!(devices.len(), 2);
     assert_eq!(devices[0].serial, "731d5853");
     assert_eq!(devices[0].state, DeviceState::Device);
-}
\ No newline at end of file
+}
+#[test]
+fn test_device_json() {
+    let device = Device::parse_list(
+        "emulator-5554 device product:sdk_phone_x86 model:Android_SDK_built_for_x86 device:generic_x86 transport_id:1\n",
+    )
+    .remove(0);
+    let json = serde_json::to_string(&device).unwrap();
+    assert_eq!(serde_json::from_str::<Device>(&json).unwrap(), device);
+}
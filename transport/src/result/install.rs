@@ -0,0 +1,30 @@
+// Output of `pm install` and `cmd package install`.
+// https://cs.android.com/android/platform/superproject/main/+/main:frameworks/base/services/core/java/com/android/server/pm/PackageManagerShellCommand.java
+
+/// Returns why the install failed, e.g. "INSTALL_FAILED_OLDER_SDK: ...", or `None` on success.
+pub(crate) fn install_failure(output: &str) -> Option<String> {
+    if output.lines().any(|line| line.trim() == "Success") {
+        return None;
+    }
+    let reason = output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Failure ["))
+        .map(|reason| reason.strip_suffix(']').unwrap_or(reason).to_string());
+    Some(reason.unwrap_or_else(|| output.trim().to_string()))
+}
+
+#[test]
+fn test_install_failure() {
+    assert_eq!(
+        install_failure("Performing Streamed Install\nSuccess\n"),
+        None
+    );
+    assert_eq!(
+        install_failure("Failure [INSTALL_FAILED_OLDER_SDK: Requires newer sdk version #34]\n"),
+        Some("INSTALL_FAILED_OLDER_SDK: Requires newer sdk version #34".to_string())
+    );
+    assert_eq!(
+        install_failure("Error: Unable to open file: /data/local/tmp/app.apk\n"),
+        Some("Error: Unable to open file: /data/local/tmp/app.apk".to_string())
+    );
+}
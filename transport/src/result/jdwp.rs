@@ -0,0 +1,175 @@
+use serde::Serialize;
+
+use crate::AdbTransportError;
+
+// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/proto/app_processes.proto
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AppProcess {
+    pub pid: i64,
+    pub debuggable: bool,
+    pub profileable: bool,
+    pub architecture: String,
+    pub waiting_for_debugger: bool,
+    pub uid: i64,
+    pub process_name: String,
+    pub package_names: Vec<String>,
+    pub user_id: i64,
+}
+
+/// Parses a `track-jdwp` message, one pid per line.
+pub(crate) fn parse_jdwp_pids(value: &str) -> Vec<u32> {
+    value
+        .lines()
+        .filter_map(|line| line.trim().parse::<u32>().ok())
+        .collect()
+}
+
+/// Decodes a `track-app` message, a serialized `AppProcesses` protobuf.
+pub(crate) fn parse_app_processes(bytes: &[u8]) -> Result<Vec<AppProcess>, AdbTransportError> {
+    let mut processes = Vec::new();
+    for field in ProtoFields::new(bytes) {
+        if let (1, ProtoValue::Bytes(entry)) = field? {
+            processes.push(parse_process_entry(entry)?);
+        }
+    }
+    Ok(processes)
+}
+fn parse_process_entry(bytes: &[u8]) -> Result<AppProcess, AdbTransportError> {
+    let mut process = AppProcess::default();
+    for field in ProtoFields::new(bytes) {
+        match field? {
+            (1, ProtoValue::Varint(pid)) => process.pid = pid as i64,
+            (2, ProtoValue::Varint(debuggable)) => process.debuggable = debuggable != 0,
+            (3, ProtoValue::Varint(profileable)) => process.profileable = profileable != 0,
+            (4, ProtoValue::Bytes(architecture)) => {
+                process.architecture = proto_string(architecture)?
+            }
+            (5, ProtoValue::Varint(waiting)) => process.waiting_for_debugger = waiting != 0,
+            (6, ProtoValue::Varint(uid)) => process.uid = uid as i64,
+            (7, ProtoValue::Bytes(name)) => process.process_name = proto_string(name)?,
+            (8, ProtoValue::Bytes(package)) => process.package_names.push(proto_string(package)?),
+            (9, ProtoValue::Varint(user_id)) => process.user_id = user_id as i64,
+            // Fields added by newer adbd versions are skipped.
+            _ => {}
+        }
+    }
+    Ok(process)
+}
+fn proto_string(bytes: &[u8]) -> Result<String, AdbTransportError> {
+    String::from_utf8(bytes.to_vec()).map_err(|err| {
+        AdbTransportError::InvalidResponse("track_app".to_string(), Some(err.to_string()))
+    })
+}
+
+enum ProtoValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+/// Walks the fields of a protobuf message, just enough of the wire format for `track-app`.
+struct ProtoFields<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+impl<'a> ProtoFields<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+    fn varint(&mut self) -> Result<u64, AdbTransportError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.bytes.get(self.pos).ok_or_else(truncated)?;
+            self.pos += 1;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(truncated())
+    }
+    fn take(&mut self, length: usize) -> Result<&'a [u8], AdbTransportError> {
+        let end = self.pos.checked_add(length).ok_or_else(truncated)?;
+        let bytes = self.bytes.get(self.pos..end).ok_or_else(truncated)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+    fn field(&mut self) -> Result<(u64, ProtoValue<'a>), AdbTransportError> {
+        let key = self.varint()?;
+        let value = match key & 0x7 {
+            0 => ProtoValue::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                ProtoValue::Fixed
+            }
+            2 => {
+                let length = self.varint()? as usize;
+                ProtoValue::Bytes(self.take(length)?)
+            }
+            5 => {
+                self.take(4)?;
+                ProtoValue::Fixed
+            }
+            wire_type => {
+                return Err(AdbTransportError::InvalidResponse(
+                    "track_app".to_string(),
+                    Some(format!("unsupported wire type {}", wire_type)),
+                ))
+            }
+        };
+        Ok((key >> 3, value))
+    }
+}
+impl<'a> Iterator for ProtoFields<'a> {
+    type Item = Result<(u64, ProtoValue<'a>), AdbTransportError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.bytes.len() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            // Stop after a malformed field, the rest cannot be framed.
+            self.pos = self.bytes.len();
+        }
+        Some(field)
+    }
+}
+fn truncated() -> AdbTransportError {
+    AdbTransportError::InvalidResponse(
+        "track_app".to_string(),
+        Some("truncated protobuf".to_string()),
+    )
+}
+
+#[test]
+fn test_jdwp_pids() {
+    assert_eq!(parse_jdwp_pids("1234\n5678\n"), vec![1234, 5678]);
+    assert!(parse_jdwp_pids("").is_empty());
+}
+#[test]
+fn test_app_processes() {
+    // AppProcesses { process: [
+    //   { pid: 1234, debuggable: true, architecture: "arm64", package_names: ["com.example"] },
+    //   { pid: 300, profileable: true },
+    // ] }
+    let bytes = [
+        0x0a, 0x19, // process, 25 bytes
+        0x08, 0xd2, 0x09, // pid 1234
+        0x10, 0x01, // debuggable
+        0x22, 0x05, b'a', b'r', b'm', b'6', b'4', // architecture
+        0x42, 0x0b, b'c', b'o', b'm', b'.', b'e', b'x', b'a', b'm', b'p', b'l',
+        b'e', // package_names
+        0x0a, 0x05, // process, 5 bytes
+        0x08, 0xac, 0x02, // pid 300
+        0x18, 0x01, // profileable
+    ];
+    let processes = parse_app_processes(&bytes).unwrap();
+    assert_eq!(processes.len(), 2);
+    assert_eq!(processes[0].pid, 1234);
+    assert!(processes[0].debuggable);
+    assert_eq!(processes[0].architecture, "arm64");
+    assert_eq!(processes[0].package_names, vec!["com.example".to_string()]);
+    assert_eq!(processes[1].pid, 300);
+    assert!(processes[1].profileable);
+    assert!(!processes[1].debuggable);
+    assert!(parse_app_processes(&bytes[..10]).is_err());
+}
@@ -0,0 +1,141 @@
+use std::fmt::Display;
+use std::net::SocketAddr;
+
+use crate::{AdbTransportError, Result};
+use nom::{
+    bytes::complete::take_while1,
+    character::complete::{space0, space1},
+};
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum MdnsServiceType {
+    /// `_adb._tcp`, devices listening with `adb tcpip`.
+    Adb,
+    /// `_adb-tls-connect._tcp`, wireless debugging devices that can be connected to.
+    TlsConnect,
+    /// `_adb-tls-pairing._tcp`, wireless debugging devices waiting to be paired.
+    TlsPairing,
+    Other(String),
+}
+impl From<&str> for MdnsServiceType {
+    fn from(value: &str) -> Self {
+        match value.trim_end_matches('.') {
+            "_adb._tcp" => MdnsServiceType::Adb,
+            "_adb-tls-connect._tcp" => MdnsServiceType::TlsConnect,
+            "_adb-tls-pairing._tcp" => MdnsServiceType::TlsPairing,
+            other => MdnsServiceType::Other(other.to_string()),
+        }
+    }
+}
+impl Display for MdnsServiceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MdnsServiceType::Adb => write!(f, "_adb._tcp"),
+            MdnsServiceType::TlsConnect => write!(f, "_adb-tls-connect._tcp"),
+            MdnsServiceType::TlsPairing => write!(f, "_adb-tls-pairing._tcp"),
+            MdnsServiceType::Other(other) => write!(f, "{}", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MdnsService {
+    pub instance: String,
+    pub service_type: MdnsServiceType,
+    pub address: SocketAddr,
+}
+impl MdnsService {
+    pub fn ip(&self) -> std::net::IpAddr {
+        self.address.ip()
+    }
+    pub fn port(&self) -> u16 {
+        self.address.port()
+    }
+    /// Parses the reply of `host:mdns:services`, one service per line.
+    pub(crate) fn parse_list(value: &str) -> Vec<MdnsService> {
+        value
+            .lines()
+            .filter_map(|line| MdnsService::try_from(line).ok())
+            .collect()
+    }
+    fn nom_parse(value: &str) -> Result<MdnsService, nom::Err<nom::error::Error<&str>>> {
+        let (value, _) = space0(value)?;
+        let (value, instance) = take_while1(is_not_whitespace)(value)?;
+        let (value, _) = space1(value)?;
+        let (value, service_type) = take_while1(is_not_whitespace)(value)?;
+        let (value, _) = space1(value)?;
+        let (_, address) = take_while1(is_not_whitespace)(value)?;
+        let address = address.parse::<SocketAddr>().map_err(|_| {
+            nom::Err::Error(nom::error::Error::new(
+                address,
+                nom::error::ErrorKind::Verify,
+            ))
+        })?;
+        Ok(MdnsService {
+            instance: instance.to_string(),
+            service_type: MdnsServiceType::from(service_type),
+            address,
+        })
+    }
+}
+impl TryFrom<&str> for MdnsService {
+    type Error = AdbTransportError;
+    fn try_from(value: &str) -> Result<Self> {
+        Self::nom_parse(value).map_err(|err| AdbTransportError::ConversionError(err.to_string()))
+    }
+}
+#[inline]
+fn is_not_whitespace(c: char) -> bool {
+    !c.is_whitespace()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MdnsCheck {
+    /// "mdns daemon version [Openscreen discovery 0.0.0]"
+    Available(String),
+    /// mDNS discovery is disabled or failed to start on the server.
+    Unavailable(String),
+}
+impl From<&str> for MdnsCheck {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        match value.strip_prefix("mdns daemon version") {
+            Some(version) => MdnsCheck::Available(
+                version
+                    .trim()
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_string(),
+            ),
+            None => MdnsCheck::Unavailable(value.to_string()),
+        }
+    }
+}
+
+#[test]
+fn test_mdns_services() {
+    let raw = "adb-1A2B3C4D-vWxYz\t_adb-tls-connect._tcp\t192.168.1.20:37777\n\
+adb-1A2B3C4D-vWxYz\t_adb-tls-pairing._tcp.\t192.168.1.20:40123\n\
+emulator\t_adb._tcp\t[fe80::1]:5555\n";
+    let services = MdnsService::parse_list(raw);
+    assert_eq!(services.len(), 3);
+    assert_eq!(services[0].instance, "adb-1A2B3C4D-vWxYz");
+    assert_eq!(services[0].service_type, MdnsServiceType::TlsConnect);
+    assert_eq!(services[0].address, "192.168.1.20:37777".parse().unwrap());
+    assert_eq!(services[1].service_type, MdnsServiceType::TlsPairing);
+    assert_eq!(services[1].port(), 40123);
+    assert_eq!(services[2].service_type, MdnsServiceType::Adb);
+    assert!(services[2].ip().is_ipv6());
+}
+#[test]
+fn test_mdns_check() {
+    assert_eq!(
+        MdnsCheck::from("mdns daemon version [Openscreen discovery 0.0.0]\n"),
+        MdnsCheck::Available("Openscreen discovery 0.0.0".to_string())
+    );
+    assert_eq!(
+        MdnsCheck::from("ERROR: mdns daemon unavailable"),
+        MdnsCheck::Unavailable("ERROR: mdns daemon unavailable".to_string())
+    );
+}
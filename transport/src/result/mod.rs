@@ -1,3 +1,11 @@
 pub mod device;
 pub mod stat;
-pub mod dent;
\ No newline at end of file
+pub mod dent;
+pub mod service;
+pub mod connect;
+pub mod mdns;
+pub mod track;
+pub mod jdwp;
+pub mod bugreport;
+pub mod install;
+pub mod properties;
@@ -0,0 +1,25 @@
+// Output of `getprop`, one "[name]: [value]" line per property.
+use std::collections::HashMap;
+
+pub(crate) fn parse_getprop(output: &str) -> HashMap<String, String> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.trim().split_once("]: [")?;
+            let name = name.strip_prefix('[')?;
+            let value = value.strip_suffix(']')?;
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[test]
+fn test_parse_getprop() {
+    let properties = parse_getprop(
+        "[ro.build.version.sdk]: [34]\n[ro.product.model]: [Pixel 8]\n[persist.sys.timezone]: []\nnoise\n",
+    );
+    assert_eq!(properties.len(), 3);
+    assert_eq!(properties["ro.build.version.sdk"], "34");
+    assert_eq!(properties["ro.product.model"], "Pixel 8");
+    assert_eq!(properties["persist.sys.timezone"], "");
+}
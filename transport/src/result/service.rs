@@ -0,0 +1,157 @@
+// Replies of the adbd services that restart or reconfigure the daemon.
+// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/daemon/restart_service.cpp
+// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/daemon/set_verity_enable_state_service.cpp
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RootReply {
+    /// "restarting adbd as root"
+    Restarting,
+    /// "adbd is already running as root"
+    AlreadyRoot,
+    /// "adbd cannot run as root in production builds"
+    ProductionBuild,
+    Other(String),
+}
+impl From<&str> for RootReply {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        if value.starts_with("restarting adbd as root") {
+            RootReply::Restarting
+        } else if value.contains("already running as root") {
+            RootReply::AlreadyRoot
+        } else if value.contains("production builds") {
+            RootReply::ProductionBuild
+        } else {
+            RootReply::Other(value.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UnrootReply {
+    /// "restarting adbd as non root"
+    Restarting,
+    /// "adbd not running as root"
+    NotRoot,
+    Other(String),
+}
+impl From<&str> for UnrootReply {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        if value.starts_with("restarting adbd as non root") {
+            UnrootReply::Restarting
+        } else if value.contains("not running as root") {
+            UnrootReply::NotRoot
+        } else {
+            UnrootReply::Other(value.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModeReply {
+    /// "restarting in TCP mode port: 5555"
+    Tcp(u16),
+    /// "restarting in USB mode"
+    Usb,
+    Other(String),
+}
+impl From<&str> for ModeReply {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        if let Some(port) = value.strip_prefix("restarting in TCP mode port:") {
+            if let Ok(port) = port.trim().parse::<u16>() {
+                return ModeReply::Tcp(port);
+            }
+        }
+        if value.starts_with("restarting in USB mode") {
+            return ModeReply::Usb;
+        }
+        ModeReply::Other(value.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemountReply {
+    /// "remount succeeded"
+    Succeeded,
+    /// Verity was disabled on the way, the remount only applies after a reboot.
+    RebootRequired,
+    Failed(String),
+}
+impl From<&str> for RemountReply {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        if value.contains("reboot") {
+            RemountReply::RebootRequired
+        } else if value.ends_with("remount succeeded") {
+            RemountReply::Succeeded
+        } else {
+            RemountReply::Failed(value.to_string())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerityReply {
+    /// Verity state changed, takes effect after the next reboot.
+    RebootRequired,
+    /// "Verity already enabled/disabled on /system"
+    Unchanged,
+    Failed(String),
+}
+impl From<&str> for VerityReply {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        if value.contains("Now reboot your device") {
+            VerityReply::RebootRequired
+        } else if value.contains("already") {
+            VerityReply::Unchanged
+        } else {
+            VerityReply::Failed(value.to_string())
+        }
+    }
+}
+
+#[test]
+fn test_service_replies() {
+    assert_eq!(
+        RootReply::from("restarting adbd as root\n"),
+        RootReply::Restarting
+    );
+    assert_eq!(
+        RootReply::from("adbd is already running as root\n"),
+        RootReply::AlreadyRoot
+    );
+    assert_eq!(
+        RootReply::from("adbd cannot run as root in production builds\n"),
+        RootReply::ProductionBuild
+    );
+    assert_eq!(
+        UnrootReply::from("adbd not running as root\n"),
+        UnrootReply::NotRoot
+    );
+    assert_eq!(
+        ModeReply::from("restarting in TCP mode port: 5555\n"),
+        ModeReply::Tcp(5555)
+    );
+    assert_eq!(ModeReply::from("restarting in USB mode\n"), ModeReply::Usb);
+    assert_eq!(
+        RemountReply::from("Using overlayfs for /system\nremount succeeded\n"),
+        RemountReply::Succeeded
+    );
+    assert_eq!(
+        VerityReply::from(
+            "Verity disabled on /system\nNow reboot your device for settings to take effect\n"
+        ),
+        VerityReply::RebootRequired
+    );
+    assert_eq!(
+        VerityReply::from("Verity already disabled on /system\n"),
+        VerityReply::Unchanged
+    );
+    assert_eq!(
+        VerityReply::from("verity cannot be disabled/enabled - USER build\n"),
+        VerityReply::Failed("verity cannot be disabled/enabled - USER build".to_string())
+    );
+}
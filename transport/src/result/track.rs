@@ -0,0 +1,116 @@
+use serde::Serialize;
+
+use crate::result::device::{Device, DeviceState};
+
+/// A change between two device lists sent by `host:track-devices`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum DeviceEvent {
+    Added(Device),
+    Removed(Device),
+    StateChanged { device: Device, old: DeviceState },
+}
+impl DeviceEvent {
+    pub fn device(&self) -> &Device {
+        match self {
+            DeviceEvent::Added(device) => device,
+            DeviceEvent::Removed(device) => device,
+            DeviceEvent::StateChanged { device, .. } => device,
+        }
+    }
+}
+
+/// What a reconnecting tracker reports: device changes, plus the server going away and
+/// coming back.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum TrackerEvent {
+    Device(DeviceEvent),
+    ServerDisconnected,
+    ServerReconnected,
+}
+
+// Devices sharing a serial are told apart by transport id when the server reports it.
+fn same_device(a: &Device, b: &Device) -> bool {
+    a.serial == b.serial && a.transport_id == b.transport_id
+}
+
+/// Computes the events that turn the `old` device list into the `new` one.
+pub fn diff_devices(old: &[Device], new: &[Device]) -> Vec<DeviceEvent> {
+    diff_by(old, new, same_device)
+}
+/// Like `diff_devices` for lists from two runs of the server. A restarted server hands
+/// out new transport ids, so devices are matched on their serial alone.
+pub fn diff_devices_across_restart(old: &[Device], new: &[Device]) -> Vec<DeviceEvent> {
+    diff_by(old, new, |a, b| a.serial == b.serial)
+}
+fn diff_by(
+    old: &[Device],
+    new: &[Device],
+    same_device: fn(&Device, &Device) -> bool,
+) -> Vec<DeviceEvent> {
+    let mut events = Vec::new();
+    for device in old {
+        if !new.iter().any(|current| same_device(device, current)) {
+            events.push(DeviceEvent::Removed(device.clone()));
+        }
+    }
+    for device in new {
+        match old.iter().find(|previous| same_device(previous, device)) {
+            None => events.push(DeviceEvent::Added(device.clone())),
+            Some(previous) if previous.state != device.state => {
+                events.push(DeviceEvent::StateChanged {
+                    device: device.clone(),
+                    old: previous.state,
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    events
+}
+
+#[test]
+fn test_diff_devices() {
+    let old =
+        Device::parse_list("731d5853\tdevice\nemulator-5554\toffline\nemulator-5556\tdevice\n");
+    let new = Device::parse_list("731d5853\tdevice\nemulator-5554\tdevice\nR58M\tunauthorized\n");
+    let events = diff_devices(&old, &new);
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0], DeviceEvent::Removed(old[2].clone()));
+    assert_eq!(
+        events[1],
+        DeviceEvent::StateChanged {
+            device: new[1].clone(),
+            old: DeviceState::Offline,
+        }
+    );
+    assert_eq!(events[2], DeviceEvent::Added(new[2].clone()));
+    assert!(diff_devices(&new, &new).is_empty());
+}
+#[test]
+fn test_diff_devices_duplicate_serials() {
+    let old = Device::parse_list("0123456789ABCDEF       device usb:1-1 transport_id:1\n");
+    let new = Device::parse_list(
+        "0123456789ABCDEF       device usb:1-1 transport_id:1\n0123456789ABCDEF       device usb:1-2 transport_id:2\n",
+    );
+    assert_eq!(
+        diff_devices(&old, &new),
+        vec![DeviceEvent::Added(new[1].clone())]
+    );
+}
+#[test]
+fn test_diff_devices_across_restart() {
+    let old = Device::parse_list(
+        "731d5853\tdevice usb:1-1 transport_id:1\nemulator-5554\tdevice usb:1-2 transport_id:2\n",
+    );
+    let new = Device::parse_list(
+        "731d5853\tdevice usb:1-1 transport_id:7\nemulator-5554\toffline usb:1-2 transport_id:8\n",
+    );
+    assert_eq!(diff_devices(&old, &new).len(), 4);
+    assert_eq!(
+        diff_devices_across_restart(&old, &new),
+        vec![DeviceEvent::StateChanged {
+            device: new[1].clone(),
+            old: DeviceState::Device,
+        }]
+    );
+}
@@ -0,0 +1,193 @@
+use std::fmt::Display;
+use std::net::Ipv6Addr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::AdbTransportError;
+
+pub const DEFAULT_SERVER_PORT: u16 = 5037;
+
+/// Where the adb server listens, in the grammar of `ADB_SERVER_SOCKET`.
+// https://cs.android.com/android/platform/superproject/main/+/main:packages/modules/adb/socket_spec.cpp
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerAddress {
+    /// "tcp:port", "tcp:host:port" or "tcp:[v6]:port", the host may be a DNS name.
+    Tcp { host: String, port: u16 },
+    /// "localfilesystem:/path", or a bare path starting with "/" or "./".
+    LocalFilesystem(PathBuf),
+    /// "localabstract:name", a Linux abstract namespace socket.
+    LocalAbstract(String),
+    /// "tls://host:port", TCP wrapped in TLS, needs the `tls` feature to connect.
+    Tls { host: String, port: u16 },
+}
+impl ServerAddress {
+    /// The address in `ADB_SERVER_SOCKET`, or the default local server.
+    pub fn from_env() -> Result<Self, AdbTransportError> {
+        match std::env::var("ADB_SERVER_SOCKET") {
+            Ok(addr) if !addr.is_empty() => addr.parse(),
+            _ => Ok(Self::default()),
+        }
+    }
+}
+impl Default for ServerAddress {
+    fn default() -> Self {
+        ServerAddress::Tcp {
+            host: "localhost".to_string(),
+            port: DEFAULT_SERVER_PORT,
+        }
+    }
+}
+impl FromStr for ServerAddress {
+    type Err = AdbTransportError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid =
+            |reason: &str| AdbTransportError::InvalidAddress(format!("{}: {}", s, reason));
+        if let Some(path) = s.strip_prefix("localfilesystem:") {
+            if path.is_empty() {
+                return Err(invalid("empty path"));
+            }
+            return Ok(ServerAddress::LocalFilesystem(PathBuf::from(path)));
+        }
+        if let Some(name) = s.strip_prefix("localabstract:") {
+            if name.is_empty() {
+                return Err(invalid("empty name"));
+            }
+            return Ok(ServerAddress::LocalAbstract(name.to_string()));
+        }
+        if s.starts_with('/') || s.starts_with("./") {
+            return Ok(ServerAddress::LocalFilesystem(PathBuf::from(s)));
+        }
+        if let Some(addr) = s.strip_prefix("tls://") {
+            let (host, port) = parse_host_port(addr, invalid)?;
+            return Ok(ServerAddress::Tls { host, port });
+        }
+        // Without a scheme the address is taken as TCP, like it always was.
+        let (host, port) = parse_host_port(s.strip_prefix("tcp:").unwrap_or(s), invalid)?;
+        Ok(ServerAddress::Tcp { host, port })
+    }
+}
+/// Splits "host:port", "[v6]:port", "port" or "host", filling in localhost and 5037.
+fn parse_host_port(
+    addr: &str,
+    invalid: impl Fn(&str) -> AdbTransportError,
+) -> Result<(String, u16), AdbTransportError> {
+    let (host, port) = if let Some(rest) = addr.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']').ok_or_else(|| invalid("missing ']'"))?;
+        match rest {
+            "" => (host, None),
+            rest => (
+                host,
+                Some(
+                    rest.strip_prefix(':')
+                        .ok_or_else(|| invalid("expected ':port'"))?,
+                ),
+            ),
+        }
+    } else if addr.parse::<Ipv6Addr>().is_ok() {
+        (addr, None)
+    } else {
+        match addr.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            // "tcp:5037" names a port on localhost.
+            None if addr.bytes().all(|byte| byte.is_ascii_digit()) => ("", Some(addr)),
+            None => (addr, None),
+        }
+    };
+    let port = match port {
+        Some(port) => port
+            .parse::<u16>()
+            .map_err(|err| invalid(&err.to_string()))?,
+        None => DEFAULT_SERVER_PORT,
+    };
+    let host = match host {
+        "" => "localhost",
+        host if host.contains(':') && host.parse::<Ipv6Addr>().is_err() => {
+            return Err(invalid("invalid host"))
+        }
+        host => host,
+    };
+    Ok((host.to_string(), port))
+}
+impl Display for ServerAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerAddress::Tcp { host, port } if host.contains(':') => {
+                write!(f, "tcp:[{}]:{}", host, port)
+            }
+            ServerAddress::Tcp { host, port } => write!(f, "tcp:{}:{}", host, port),
+            ServerAddress::LocalFilesystem(path) => {
+                write!(f, "localfilesystem:{}", path.display())
+            }
+            ServerAddress::LocalAbstract(name) => write!(f, "localabstract:{}", name),
+            ServerAddress::Tls { host, port } if host.contains(':') => {
+                write!(f, "tls://[{}]:{}", host, port)
+            }
+            ServerAddress::Tls { host, port } => write!(f, "tls://{}:{}", host, port),
+        }
+    }
+}
+
+#[test]
+fn test_server_address() {
+    let tcp = |host: &str, port| ServerAddress::Tcp {
+        host: host.to_string(),
+        port,
+    };
+    assert_eq!(
+        "tcp:5037".parse::<ServerAddress>().unwrap(),
+        tcp("localhost", 5037)
+    );
+    assert_eq!(
+        "tcp:adb.lab.internal:5038"
+            .parse::<ServerAddress>()
+            .unwrap(),
+        tcp("adb.lab.internal", 5038)
+    );
+    assert_eq!(
+        "tcp:[::1]:5037".parse::<ServerAddress>().unwrap(),
+        tcp("::1", 5037)
+    );
+    assert_eq!(
+        "tcp:[::1]".parse::<ServerAddress>().unwrap(),
+        tcp("::1", 5037)
+    );
+    assert_eq!("::1".parse::<ServerAddress>().unwrap(), tcp("::1", 5037));
+    assert_eq!(
+        "127.0.0.1:5555".parse::<ServerAddress>().unwrap(),
+        tcp("127.0.0.1", 5555)
+    );
+    assert_eq!(
+        "adb-host".parse::<ServerAddress>().unwrap(),
+        tcp("adb-host", 5037)
+    );
+    assert_eq!(
+        "localfilesystem:/var/run/adb.sock"
+            .parse::<ServerAddress>()
+            .unwrap(),
+        ServerAddress::LocalFilesystem(PathBuf::from("/var/run/adb.sock"))
+    );
+    assert_eq!(
+        "/var/run/adb.sock".parse::<ServerAddress>().unwrap(),
+        ServerAddress::LocalFilesystem(PathBuf::from("/var/run/adb.sock"))
+    );
+    assert_eq!(
+        "localabstract:adb-proxy".parse::<ServerAddress>().unwrap(),
+        ServerAddress::LocalAbstract("adb-proxy".to_string())
+    );
+    assert_eq!(
+        "tls://adb.lab.internal:5038"
+            .parse::<ServerAddress>()
+            .unwrap(),
+        ServerAddress::Tls {
+            host: "adb.lab.internal".to_string(),
+            port: 5038
+        }
+    );
+    assert!("tls://[::1".parse::<ServerAddress>().is_err());
+    assert!("tcp:host:port".parse::<ServerAddress>().is_err());
+    assert!("tcp:[::1".parse::<ServerAddress>().is_err());
+    assert!("tcp:[::1]5037".parse::<ServerAddress>().is_err());
+    assert!("localfilesystem:".parse::<ServerAddress>().is_err());
+    assert_eq!(tcp("::1", 5037).to_string(), "tcp:[::1]:5037");
+}
@@ -0,0 +1,119 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::{TcpStream, UnixStream};
+
+use crate::{AdbTransportError, Result};
+
+/// Opens connections to the adb server. Every request that needs a fresh connection,
+/// and every reconnect, goes through the connector, so any stream can carry the
+/// protocol: a socket, an in-memory `tokio::io::duplex` pipe, a TLS or SSH channel.
+#[async_trait]
+pub trait Connector: Send + Sync {
+    type Stream: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static;
+    async fn connect(&self) -> Result<Self::Stream>;
+}
+
+/// Connects over TCP, resolving `host` on every connect.
+#[derive(Debug, Clone)]
+pub struct TcpConnector {
+    pub host: String,
+    pub port: u16,
+}
+impl TcpConnector {
+    pub fn new<H: ToString>(host: H, port: u16) -> Self {
+        Self {
+            host: host.to_string(),
+            port,
+        }
+    }
+}
+#[async_trait]
+impl Connector for TcpConnector {
+    type Stream = TcpStream;
+    async fn connect(&self) -> Result<TcpStream> {
+        TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .map_err(AdbTransportError::unreachable)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UnixConnector {
+    pub path: PathBuf,
+}
+impl UnixConnector {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self { path: path.into() }
+    }
+}
+#[async_trait]
+impl Connector for UnixConnector {
+    type Stream = UnixStream;
+    async fn connect(&self) -> Result<UnixStream> {
+        UnixStream::connect(&self.path)
+            .await
+            .map_err(AdbTransportError::unreachable)
+    }
+}
+
+/// Connects to a Linux abstract namespace socket, which has a name but no file.
+#[derive(Debug, Clone)]
+pub struct AbstractConnector {
+    pub name: String,
+}
+impl AbstractConnector {
+    /// `name` may carry the leading '@' tools like `ss` print for abstract sockets.
+    pub fn new<N: ToString>(name: N) -> Self {
+        let name = name.to_string();
+        Self {
+            name: name.strip_prefix('@').unwrap_or(&name).to_string(),
+        }
+    }
+}
+#[cfg(target_os = "linux")]
+#[async_trait]
+impl Connector for AbstractConnector {
+    type Stream = UnixStream;
+    async fn connect(&self) -> Result<UnixStream> {
+        use std::os::linux::net::SocketAddrExt;
+        let addr = std::os::unix::net::SocketAddr::from_abstract_name(self.name.as_bytes())?;
+        // Connecting a local socket does not block, so the std call is fine here.
+        let stream = std::os::unix::net::UnixStream::connect_addr(&addr)
+            .map_err(AdbTransportError::unreachable)?;
+        stream.set_nonblocking(true)?;
+        Ok(UnixStream::from_std(stream)?)
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_abstract_connector() {
+    use std::os::linux::net::SocketAddrExt;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let name = format!("adb-client-test-{}", std::process::id());
+    let addr = std::os::unix::net::SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+    let listener = std::os::unix::net::UnixListener::bind_addr(&addr).unwrap();
+    listener.set_nonblocking(true).unwrap();
+    let listener = tokio::net::UnixListener::from_std(listener).unwrap();
+
+    let connector = AbstractConnector::new(format!("@{}", name));
+    assert_eq!(connector.name, name);
+    // Every connect, reconnects included, reaches the listener again.
+    for _ in 0..2 {
+        let mut client = connector.connect().await.unwrap();
+        let (mut server, _) = listener.accept().await.unwrap();
+        server.write_all(b"OKAY").await.unwrap();
+        let mut status = [0u8; 4];
+        client.read_exact(&mut status).await.unwrap();
+        assert_eq!(&status, b"OKAY");
+    }
+    assert!(matches!(
+        AbstractConnector::new("adb-client-test-missing")
+            .connect()
+            .await,
+        Err(AdbTransportError::ServerUnreachable(_))
+    ));
+}
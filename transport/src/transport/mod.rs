@@ -1,3 +1,7 @@
+pub mod address;
+pub mod connector;
+pub mod stream_transport;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[allow(clippy::module_inception)]
 pub mod transport;
-pub mod unix_stream_transport;
-pub mod tcp_transport;
\ No newline at end of file
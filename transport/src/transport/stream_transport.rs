@@ -0,0 +1,339 @@
+use std::io::ErrorKind;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use super::connector::Connector;
+use super::transport::{read_frame, within, write_frame, AdbTransport};
+use crate::codec::{HexCodec, HostCodec};
+use crate::options::timeout::Timeouts;
+use crate::{AdbCommand, AdbTransportError, Result};
+
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+/// Speaks the adb protocol over any stream the connector opens.
+pub struct StreamTransport<S> {
+    connector: Arc<dyn Connector<Stream = S>>,
+    timeouts: Timeouts,
+    /// Dropped after a timeout, the server may still answer the abandoned request so
+    /// nothing more is read from it until the next reconnect.
+    stream: Option<S>,
+}
+impl<S: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static> StreamTransport<S> {
+    pub async fn with_timeouts(
+        connector: Arc<dyn Connector<Stream = S>>,
+        timeouts: Timeouts,
+    ) -> Result<Self> {
+        let stream = within(timeouts.connect, "connecting", connector.connect()).await?;
+        Ok(Self {
+            connector,
+            timeouts,
+            stream: Some(stream),
+        })
+    }
+    fn stream(&mut self) -> Result<&mut S> {
+        self.stream.as_mut().ok_or_else(|| {
+            std::io::Error::new(
+                ErrorKind::NotConnected,
+                "connection closed after a timeout, reconnect first",
+            )
+            .into()
+        })
+    }
+    fn close_on_timeout<T>(&mut self, result: Result<T>) -> Result<T> {
+        if let Err(AdbTransportError::Timeout(_)) = result {
+            self.stream = None;
+        }
+        result
+    }
+}
+#[async_trait]
+impl<S: AsyncRead + AsyncWrite + Unpin + Send + Sync + 'static> AdbTransport
+    for StreamTransport<S>
+{
+    async fn reconnect(&mut self) -> Result<()> {
+        self.stream = None;
+        let connect = self.connector.connect();
+        self.stream = Some(within(self.timeouts.connect, "connecting", connect).await?);
+        Ok(())
+    }
+    async fn duplicate(&self) -> Result<Box<dyn AdbTransport>> {
+        Ok(Box::new(
+            Self::with_timeouts(self.connector.clone(), self.timeouts.clone()).await?,
+        ))
+    }
+    #[async_backtrace::framed]
+    async fn send_command(&mut self, command: AdbCommand, wait_for_resp: bool) -> Result<Vec<u8>> {
+        let limit = self.timeouts.request;
+        let what = command.to_string();
+        let request = async {
+            write_frame(self, &mut HostCodec, command).await?;
+            read_frame(self, &mut HostCodec).await?.into_result()?;
+            if wait_for_resp {
+                return Ok(read_frame(self, &mut HexCodec).await?.to_vec());
+            }
+            Ok(vec![])
+        };
+        let result = within(limit, &what, request).await;
+        self.close_on_timeout(result)
+    }
+    #[async_backtrace::framed]
+    async fn read_exact_(&mut self, buffer: &mut [u8]) -> Result<(), AdbTransportError> {
+        let limit = self.timeouts.idle_read;
+        let stream = self.stream()?;
+        let result = within(limit, "read", async {
+            stream.read_exact(buffer).await?;
+            Ok(())
+        })
+        .await;
+        self.close_on_timeout(result)
+    }
+    async fn read_(&mut self, buffer: &mut [u8]) -> Result<usize, AdbTransportError> {
+        let limit = self.timeouts.idle_read;
+        let stream = self.stream()?;
+        let result = within(limit, "read", async { Ok(stream.read(buffer).await?) }).await;
+        self.close_on_timeout(result)
+    }
+    async fn read_to_end(&mut self) -> Result<Vec<u8>> {
+        // Read in chunks so the idle timeout applies between them, not to the whole stream.
+        let mut output = vec![];
+        let mut buffer = vec![0u8; READ_CHUNK_SIZE];
+        loop {
+            let read = self.read_(&mut buffer).await?;
+            if read == 0 {
+                return Ok(output);
+            }
+            output.extend_from_slice(&buffer[..read]);
+        }
+    }
+    #[async_backtrace::framed]
+    async fn write_all(&mut self, data: &[u8]) -> Result<()> {
+        let limit = self.timeouts.write;
+        let stream = self.stream()?;
+        let result = within(limit, "write", async {
+            stream.write_all(data).await?;
+            Ok(())
+        })
+        .await;
+        self.close_on_timeout(result)
+    }
+    fn set_timeouts(&mut self, timeouts: Timeouts) {
+        self.timeouts = timeouts;
+    }
+}
+
+/// An adb server answering each request of a connection with `reply(request)`. It logs
+/// every connect and request. With a `service`, a device service such as `shell,raw:`,
+/// `exec:` or `sync:` is handed the stream after its reply, like adbd would be. With a
+/// `script`, each connect follows the next entry instead, the last one repeating.
+#[cfg(test)]
+pub(crate) struct FakeServer {
+    reply: fn(&str) -> Vec<u8>,
+    service: Option<FakeService>,
+    script: Vec<FakeConnect>,
+    connects: std::sync::atomic::AtomicUsize,
+    pub(crate) log: Arc<std::sync::Mutex<Vec<String>>>,
+}
+#[cfg(test)]
+pub(crate) type FakeService =
+    fn(String, tokio::io::DuplexStream) -> futures_util::future::BoxFuture<'static, ()>;
+/// What one connect to a scripted `FakeServer` does.
+#[cfg(test)]
+#[derive(Clone)]
+pub(crate) enum FakeConnect {
+    /// Refuses the connection, like a server that is not running.
+    Refuse,
+    /// Answers every request with `reply`, and goes away after the first one with
+    /// `hang_up`, like a server that is killed.
+    Answer { reply: Vec<u8>, hang_up: bool },
+}
+#[cfg(test)]
+impl FakeServer {
+    pub(crate) fn new(reply: fn(&str) -> Vec<u8>) -> Self {
+        Self {
+            reply,
+            service: None,
+            script: Vec::new(),
+            connects: Default::default(),
+            log: Default::default(),
+        }
+    }
+    pub(crate) fn with_service(mut self, service: FakeService) -> Self {
+        self.service = Some(service);
+        self
+    }
+    pub(crate) fn with_script(mut self, script: Vec<FakeConnect>) -> Self {
+        self.script = script;
+        self
+    }
+}
+#[cfg(test)]
+#[async_trait]
+impl Connector for FakeServer {
+    type Stream = tokio::io::DuplexStream;
+    async fn connect(&self) -> Result<Self::Stream> {
+        let connect = self
+            .connects
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let (answer, hang_up) = match self.script.get(connect).or(self.script.last()) {
+            Some(FakeConnect::Refuse) => {
+                return Err(AdbTransportError::unreachable(
+                    ErrorKind::ConnectionRefused.into(),
+                ))
+            }
+            Some(FakeConnect::Answer { reply, hang_up }) => (Some(reply.clone()), *hang_up),
+            None => (None, false),
+        };
+        let (client, mut server) = tokio::io::duplex(4096);
+        let reply = self.reply;
+        let service = self.service;
+        let log = self.log.clone();
+        log.lock().unwrap().push("connect".to_string());
+        tokio::spawn(async move {
+            let mut length = [0u8; 4];
+            while server.read_exact(&mut length).await.is_ok() {
+                let length = std::str::from_utf8(&length).unwrap();
+                let mut request = vec![0u8; usize::from_str_radix(length, 16).unwrap()];
+                server.read_exact(&mut request).await.unwrap();
+                let request = std::str::from_utf8(&request).unwrap().to_string();
+                log.lock().unwrap().push(request.clone());
+                let answer = answer.clone().unwrap_or_else(|| reply(&request));
+                if server.write_all(&answer).await.is_err() || hang_up {
+                    break;
+                }
+                match service {
+                    Some(service) if !request.starts_with("host") => {
+                        return service(request, server).await;
+                    }
+                    _ => {}
+                }
+            }
+        });
+        Ok(client)
+    }
+}
+#[tokio::test]
+async fn test_duplex_devices() {
+    let server = FakeServer::new(|request| match request {
+        "host:devices" => b"OKAY0015emulator-5554\tdevice\n".to_vec(),
+        _ => b"FAIL0014unknown host service".to_vec(),
+    });
+    let adb = crate::AdbTransports::with_connector(server).await.unwrap();
+    let devices = adb.devices().await.unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].serial, "emulator-5554");
+}
+#[tokio::test]
+async fn test_duplex_fail() {
+    let server = FakeServer::new(|request| match request {
+        "host-serial:abc:get-state" => b"FAIL0016device 'abc' not found".to_vec(),
+        _ => b"FAIL0014unknown host service".to_vec(),
+    });
+    let adb = crate::AdbTransports::with_connector(server).await.unwrap();
+    let state = adb
+        .get_state(crate::DeviceSelector::Serial("abc".to_string()))
+        .await;
+    assert!(matches!(state, Err(AdbTransportError::DeviceNotFound(serial)) if serial == "abc"));
+}
+#[tokio::test]
+async fn test_request_timeout() {
+    let server = FakeServer::new(|request| match request {
+        "host:devices" => b"OKAY0015emulator-5554\tdevice\n".to_vec(),
+        // Never answers, like a server stuck on an unresponsive device.
+        _ => vec![],
+    });
+    let mut transport = StreamTransport::with_timeouts(
+        Arc::new(server),
+        Timeouts {
+            request: Some(std::time::Duration::from_millis(50)),
+            ..Timeouts::default()
+        },
+    )
+    .await
+    .unwrap();
+    let result = transport.send_command(AdbCommand::Version, true).await;
+    assert!(
+        matches!(result, Err(AdbTransportError::Timeout(what)) if what.starts_with("host:version"))
+    );
+    // The connection is closed rather than left mid-request.
+    assert!(matches!(
+        transport.send_command(AdbCommand::Devices, true).await,
+        Err(AdbTransportError::IoError(err)) if err.kind() == ErrorKind::NotConnected
+    ));
+    transport.reconnect().await.unwrap();
+    let devices = transport
+        .send_command(AdbCommand::Devices, true)
+        .await
+        .unwrap();
+    assert_eq!(devices, b"emulator-5554\tdevice\n");
+}
+#[tokio::test]
+async fn test_write_timeout() {
+    // adbd opens the service, then stops reading, like a stalled device.
+    let server = FakeServer::new(|_| b"OKAY".to_vec()).with_service(|_, stream| {
+        Box::pin(async move {
+            let _stream = stream;
+            std::future::pending::<()>().await
+        })
+    });
+    let mut transport = StreamTransport::with_timeouts(
+        Arc::new(server),
+        Timeouts {
+            write: Some(std::time::Duration::from_millis(50)),
+            ..Timeouts::default()
+        },
+    )
+    .await
+    .unwrap();
+    transport
+        .send_command(AdbCommand::Exec("cat".to_string()), false)
+        .await
+        .unwrap();
+    let result = transport.write_all(&[0u8; 64 * 1024]).await;
+    assert!(matches!(result, Err(AdbTransportError::Timeout(what)) if what.starts_with("write")));
+    assert!(matches!(
+        transport.write_all(b"more").await,
+        Err(AdbTransportError::IoError(err)) if err.kind() == ErrorKind::NotConnected
+    ));
+}
+#[tokio::test]
+async fn test_session_reselects_device() {
+    use crate::{DeviceSelector, SessionState};
+
+    let server = FakeServer::new(|request| match request {
+        "host:transport:gone" => b"FAIL0017device 'gone' not found".to_vec(),
+        _ => b"OKAY".to_vec(),
+    });
+    let log = server.log.clone();
+    let mut adb = crate::AdbTransports::with_connector(server).await.unwrap();
+    let a = DeviceSelector::Serial("A".to_string());
+    let b = DeviceSelector::Serial("B".to_string());
+    adb.may_set_sync(a.clone()).await.unwrap();
+    adb.may_set_sync(a.clone()).await.unwrap();
+    assert_eq!(adb.state(), &SessionState::SyncMode(a.clone()));
+    // Another device gets its own connection instead of reusing A's sync session.
+    adb.may_set_sync(b.clone()).await.unwrap();
+    assert_eq!(adb.state(), &SessionState::SyncMode(b));
+    assert!(adb
+        .may_set_device(DeviceSelector::Serial("gone".to_string()))
+        .await
+        .is_err());
+    assert_eq!(adb.state(), &SessionState::Consumed);
+    adb.may_set_device(a.clone()).await.unwrap();
+    assert_eq!(adb.state(), &SessionState::Transport(a));
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "connect",
+            "host:transport:A",
+            "sync:",
+            "connect",
+            "host:transport:B",
+            "sync:",
+            "connect",
+            "host:transport:gone",
+            "connect",
+            "host:transport:A",
+        ]
+    );
+}
@@ -0,0 +1,211 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use rustls::crypto::{ring, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::TlsAcceptor;
+
+use super::connector::{Connector, TcpConnector};
+use crate::{AdbTransportError, Result};
+
+/// Certificates for a TLS connection to a remote adb server.
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    /// PEM file with the CAs that sign the server certificate, the public web roots when unset.
+    pub ca_file: Option<PathBuf>,
+    /// PEM certificate chain presented to servers that require client authentication.
+    pub cert_file: Option<PathBuf>,
+    /// PEM private key of `cert_file`.
+    pub key_file: Option<PathBuf>,
+}
+impl TlsOptions {
+    fn client_config(&self) -> Result<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        match &self.ca_file {
+            Some(ca_file) => add_certs(&mut roots, ca_file)?,
+            None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        }
+        let builder = ClientConfig::builder_with_provider(provider())
+            .with_safe_default_protocol_versions()
+            .map_err(tls_error)?
+            .with_root_certificates(roots);
+        match (&self.cert_file, &self.key_file) {
+            (Some(cert_file), Some(key_file)) => builder
+                .with_client_auth_cert(load_certs(cert_file)?, load_key(key_file)?)
+                .map_err(tls_error),
+            (None, None) => Ok(builder.with_no_client_auth()),
+            _ => Err(AdbTransportError::Tls(
+                "a client certificate needs both a certificate and a key file".to_string(),
+            )),
+        }
+    }
+}
+
+/// Connects over TCP and verifies the server certificate against `host`.
+#[derive(Clone)]
+pub struct TlsConnector {
+    tcp: TcpConnector,
+    server_name: ServerName<'static>,
+    connector: tokio_rustls::TlsConnector,
+}
+impl TlsConnector {
+    pub fn new<H: ToString>(host: H, port: u16, options: &TlsOptions) -> Result<Self> {
+        let host = host.to_string();
+        let server_name = ServerName::try_from(host.clone()).map_err(tls_error)?;
+        Ok(Self {
+            tcp: TcpConnector::new(host, port),
+            server_name,
+            connector: Arc::new(options.client_config()?).into(),
+        })
+    }
+}
+#[async_trait]
+impl Connector for TlsConnector {
+    type Stream = TlsStream<TcpStream>;
+    async fn connect(&self) -> Result<Self::Stream> {
+        let stream = self.tcp.connect().await?;
+        self.connector
+            .connect(self.server_name.clone(), stream)
+            .await
+            .map_err(tls_error)
+    }
+}
+
+/// The listener side, for a proxy that serves the adb protocol over TLS. Clients must
+/// present a certificate signed by a CA in `client_ca_file` when it is given.
+pub fn tls_acceptor(
+    cert_file: &Path,
+    key_file: &Path,
+    client_ca_file: Option<&Path>,
+) -> Result<TlsAcceptor> {
+    let builder = ServerConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(tls_error)?;
+    let builder = match client_ca_file {
+        Some(client_ca_file) => {
+            let mut roots = RootCertStore::empty();
+            add_certs(&mut roots, client_ca_file)?;
+            let verifier = WebPkiClientVerifier::builder_with_provider(roots.into(), provider())
+                .build()
+                .map_err(tls_error)?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let config = builder
+        .with_single_cert(load_certs(cert_file)?, load_key(key_file)?)
+        .map_err(tls_error)?;
+    Ok(Arc::new(config).into())
+}
+
+// The provider is explicit so the process wide default, if any, does not matter.
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+fn tls_error(err: impl ToString) -> AdbTransportError {
+    AdbTransportError::Tls(err.to_string())
+}
+fn open(path: &Path) -> Result<BufReader<File>> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|err| AdbTransportError::Tls(format!("{}: {}", path.display(), err)))
+}
+fn load_certs(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let certs = rustls_pemfile::certs(&mut open(path)?)
+        .collect::<std::io::Result<Vec<_>>>()
+        .map_err(tls_error)?;
+    if certs.is_empty() {
+        return Err(AdbTransportError::Tls(format!(
+            "{}: no certificates",
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+fn load_key(path: &Path) -> Result<PrivateKeyDer<'static>> {
+    rustls_pemfile::private_key(&mut open(path)?)
+        .map_err(tls_error)?
+        .ok_or_else(|| AdbTransportError::Tls(format!("{}: no private key", path.display())))
+}
+fn add_certs(roots: &mut RootCertStore, path: &Path) -> Result<()> {
+    for cert in load_certs(path)? {
+        roots.add(cert).map_err(tls_error)?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_tls_client_auth() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let testdata = Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/tls");
+    let acceptor = tls_acceptor(
+        &testdata.join("server.pem"),
+        &testdata.join("server.key"),
+        Some(&testdata.join("ca.pem")),
+    )
+    .unwrap();
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                let mut request = [0u8; 16];
+                if stream.read_exact(&mut request).await.is_ok() {
+                    assert_eq!(&request, b"000chost:devices");
+                    stream
+                        .write_all(b"OKAY0015emulator-5554\tdevice\n")
+                        .await
+                        .unwrap();
+                    stream.shutdown().await.unwrap();
+                }
+            });
+        }
+    });
+
+    let options = TlsOptions {
+        ca_file: Some(testdata.join("ca.pem")),
+        cert_file: Some(testdata.join("client.pem")),
+        key_file: Some(testdata.join("client.key")),
+    };
+    let connector = TlsConnector::new("localhost", port, &options).unwrap();
+    let adb = crate::AdbTransports::with_connector(connector)
+        .await
+        .unwrap();
+    let devices = adb.devices().await.unwrap();
+    assert_eq!(devices[0].serial, "emulator-5554");
+
+    // Without a client certificate the server ends the handshake.
+    let anonymous = TlsOptions {
+        ca_file: options.ca_file.clone(),
+        ..Default::default()
+    };
+    let mut stream = TlsConnector::new("localhost", port, &anonymous)
+        .unwrap()
+        .connect()
+        .await
+        .unwrap();
+    let mut reply = [0u8; 4];
+    let rejected = match stream.write_all(b"000chost:devices").await {
+        Ok(()) => stream.read_exact(&mut reply).await.is_err(),
+        Err(_) => true,
+    };
+    assert!(rejected);
+    // The server certificate does not match another name.
+    let wrong_name = TlsConnector::new("127.0.0.1", port, &options).unwrap();
+    assert!(matches!(
+        wrong_name.connect().await,
+        Err(AdbTransportError::Tls(_))
+    ));
+}
@@ -1,21 +1,69 @@
-use anyhow::Result;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::Result;
 use async_trait::async_trait;
-use bytes::{BufMut, BytesMut};
+use bytes::BytesMut;
+use tokio_util::codec::{Decoder, Encoder};
 
-use crate::{AdbCommand, AdbSyncModeCommand, AdbTransportError};
+use crate::codec::FrameLength;
+use crate::options::timeout::Timeouts;
+use crate::{AdbCommand, AdbTransportError};
 #[async_trait]
-pub trait AdbTransport {
+pub trait AdbTransport: Send + Sync {
     async fn reconnect(&mut self) -> Result<()>;
+    /// Opens another connection to the same server, leaving this one untouched.
+    async fn duplicate(&self) -> Result<Box<dyn AdbTransport>>;
     async fn send_command(&mut self, command: AdbCommand, wait_for_resp: bool) -> Result<Vec<u8>>;
-    async fn send_sync_command(
-        &mut self,
-        command: AdbSyncModeCommand,
-    ) -> Result<(), AdbTransportError>;
-    async fn get_length(&mut self) -> Result<usize, AdbTransportError>;
-    async fn read_exact(&mut self, length: usize) -> Result<Vec<u8>, AdbTransportError>;
     async fn read_exact_(&mut self, buffer: &mut [u8]) -> Result<(), AdbTransportError>;
     async fn read_(&mut self, buffer: &mut [u8]) -> Result<usize, AdbTransportError>;
-    async fn read_buf_(&mut self, buffer: &mut BytesMut) -> Result<usize, AdbTransportError>;
     async fn read_to_end(&mut self) -> Result<Vec<u8>>;
     async fn write_all(&mut self, data: &[u8]) -> Result<()>;
+    /// Applies to the next operations and to duplicated connections.
+    fn set_timeouts(&mut self, timeouts: Timeouts);
+}
+/// Reads exactly one frame of `decoder`, leaving the bytes after it in the transport.
+pub(crate) async fn read_frame<T, D>(transport: &mut T, decoder: &mut D) -> Result<D::Item>
+where
+    T: AdbTransport + ?Sized,
+    D: Decoder<Error = AdbTransportError> + FrameLength,
+{
+    let mut buffer = BytesMut::new();
+    loop {
+        let wanted = decoder.frame_len(&buffer)?;
+        if buffer.len() >= wanted {
+            // The decoder asked for nothing more yet could not decode, do not spin.
+            return Err(AdbTransportError::InvalidResponse(
+                "read_frame".to_string(),
+                Some(String::from_utf8_lossy(&buffer).to_string()),
+            ));
+        }
+        let start = buffer.len();
+        buffer.resize(wanted, 0);
+        transport.read_exact_(&mut buffer[start..]).await?;
+        if let Some(frame) = decoder.decode(&mut buffer)? {
+            return Ok(frame);
+        }
+    }
+}
+pub(crate) async fn write_frame<T, E, I>(transport: &mut T, encoder: &mut E, item: I) -> Result<()>
+where
+    T: AdbTransport + ?Sized,
+    E: Encoder<I, Error = AdbTransportError>,
+{
+    let mut buffer = BytesMut::new();
+    encoder.encode(item, &mut buffer)?;
+    transport.write_all(&buffer).await
+}
+/// Runs `operation`, failing with a `Timeout` for `what` once `limit` has elapsed.
+pub(crate) async fn within<T, F>(limit: Option<Duration>, what: &str, operation: F) -> Result<T>
+where
+    F: Future<Output = Result<T>>,
+{
+    match limit {
+        Some(limit) => tokio::time::timeout(limit, operation)
+            .await
+            .map_err(|_| AdbTransportError::Timeout(format!("{} after {:?}", what, limit)))?,
+        None => operation.await,
+    }
 }